@@ -1,4 +1,9 @@
-use wasm_zkp_challenge::msm::{generate_msm_inputs, compute_msm};
+use wasm_zkp_challenge::msm::{
+    generate_msm_inputs, compute_msm, multi_scalar_mul, multi_scalar_mul_montgomery, multi_scalar_mul_nofilter,
+    multi_scalar_mul_with_reduction, ReductionMode,
+};
+use ark_ff::{PrimeField, Zero};
+use ark_bls12_381::Fr;
 use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
 
 fn bench_pippenger_msm(c: &mut Criterion) {
@@ -21,5 +26,98 @@ fn bench_pippenger_msm(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, bench_pippenger_msm);
+fn bench_zero_filter(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bench_zero_filter");
+    let size = 12;
+    for zeros_per_16 in [0, 8, 15].iter() {
+        let (point_vec, mut scalar_vec) = generate_msm_inputs(1<<size);
+        scalar_vec.iter_mut().enumerate()
+            .filter(|(i, _)| i % 16 < *zeros_per_16)
+            .for_each(|(_, s)| *s = Fr::zero().into_repr());
+        let input = (point_vec, scalar_vec);
+
+        group.bench_with_input(
+            BenchmarkId::new("filtered", format!("{}/16 zero scalars", zeros_per_16)),
+            &input,
+            |b, input| {
+                b.iter(|| multi_scalar_mul(&input.0, &input.1))
+            }
+        );
+        group.bench_with_input(
+            BenchmarkId::new("unfiltered", format!("{}/16 zero scalars", zeros_per_16)),
+            &input,
+            |b, input| {
+                b.iter(|| multi_scalar_mul_nofilter(&input.0, &input.1))
+            }
+        );
+    }
+}
+
+fn bench_montgomery_conversion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bench_montgomery_conversion");
+    let size = 12;
+    let (point_vec, scalar_vec) = generate_msm_inputs(1<<size);
+    let field_vec: Vec<Fr> = scalar_vec.iter().map(|s| Fr::from_repr(*s).unwrap()).collect();
+    let mut repr_buf = Vec::new();
+
+    group.bench_function("canonical scalars, no conversion", |b| {
+        b.iter(|| multi_scalar_mul(&point_vec, &scalar_vec))
+    });
+    group.bench_function("montgomery scalars, converted into a reused buffer", |b| {
+        b.iter(|| multi_scalar_mul_montgomery(&point_vec, &field_vec, &mut repr_buf))
+    });
+    group.bench_function("conversion alone", |b| {
+        b.iter(|| field_vec.iter().map(|s| s.into_repr()).collect::<Vec<_>>())
+    });
+}
+
+fn bench_reduction_mode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bench_reduction_mode");
+    for size in [8, 12].iter() {
+        let input = generate_msm_inputs(1<<size);
+
+        for mode in [ReductionMode::Projective, ReductionMode::BatchNormalizedMixed].iter() {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{:?}", mode), format!("input vector length: 2^{}", size)),
+                &input,
+                |b, input| {
+                    b.iter(|| multi_scalar_mul_with_reduction(&input.0, &input.1, *mode))
+                }
+            );
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+fn bench_skewed_windows(c: &mut Criterion) {
+    use wasm_zkp_challenge::msm::{default_tile_len, multi_scalar_mul_tiled, MsmEngine};
+
+    let mut group = c.benchmark_group("bench_skewed_windows");
+    let size = 14;
+    let (point_vec, mut scalar_vec) = generate_msm_inputs(1<<size);
+    // 32-bit scalars only populate the lowest windows.
+    scalar_vec.iter_mut().enumerate().for_each(|(i, s)| *s = (i as u64 * 0x9e37_79b9 % (1 << 32)).into());
+
+    let pool = rayon::ThreadPoolBuilder::new().build().unwrap();
+    let mut engine = MsmEngine::with_thread_pool(pool);
+    group.bench_function("parallel windows", |b| {
+        b.iter(|| engine.msm(&point_vec, &scalar_vec))
+    });
+    let tile_len = default_tile_len(point_vec.len());
+    group.bench_function("work-stealing tiles", |b| {
+        b.iter(|| multi_scalar_mul_tiled(&point_vec, &scalar_vec, tile_len))
+    });
+}
+
+#[cfg(not(feature = "parallel"))]
+fn bench_skewed_windows(_: &mut Criterion) {}
+
+criterion_group!(
+    benches,
+    bench_pippenger_msm,
+    bench_zero_filter,
+    bench_montgomery_conversion,
+    bench_reduction_mode,
+    bench_skewed_windows
+);
 criterion_main!(benches);
@@ -0,0 +1,53 @@
+use wasm_zkp_challenge::msm::random_subgroup_points;
+use wasm_zkp_challenge::point_add::{batch_affine_point_addition, generate_point_add_inputs, mixed_point_addition};
+use ark_bls12_381::G2Affine;
+use ark_std::rand::Rng;
+use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+
+// Batch affine addition trades one inversion per pair for a single shared
+// one, plus a few multiplications per pair, so it only wins once the batch is
+// large enough; sweeping the batch size shows where on this platform.
+fn bench_point_add(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bench_point_add");
+    for size in [2, 4, 6, 8, 10, 12].iter() {
+        let (points, first_idx, second_idx) = generate_point_add_inputs(1<<size);
+        let input = black_box((points, first_idx, second_idx));
+
+        group.bench_with_input(
+            BenchmarkId::new("mixed", format!("batch size: 2^{}", size)),
+            &input,
+            |b, input| b.iter(|| mixed_point_addition(&input.0, &input.1, &input.2)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("batch affine", format!("batch size: 2^{}", size)),
+            &input,
+            |b, input| b.iter(|| batch_affine_point_addition(&input.0, &input.1, &input.2)),
+        );
+    }
+}
+
+// Same sweep over BLS12-381 G2, whose `Fp2` arithmetic is dearer.
+fn bench_point_add_g2(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bench_point_add_g2");
+    let mut rng = ark_std::test_rng();
+    for size in [2, 4, 6, 8, 10].iter() {
+        let points: Vec<G2Affine> = random_subgroup_points(1<<size, &mut rng);
+        let first_idx: Vec<usize> = (0..1<<size).map(|_| rng.gen_range(0..1<<size)).collect();
+        let second_idx: Vec<usize> = (0..1<<size).map(|_| rng.gen_range(0..1<<size)).collect();
+        let input = black_box((points, first_idx, second_idx));
+
+        group.bench_with_input(
+            BenchmarkId::new("mixed", format!("batch size: 2^{}", size)),
+            &input,
+            |b, input| b.iter(|| mixed_point_addition(&input.0, &input.1, &input.2)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("batch affine", format!("batch size: 2^{}", size)),
+            &input,
+            |b, input| b.iter(|| batch_affine_point_addition(&input.0, &input.1, &input.2)),
+        );
+    }
+}
+
+criterion_group!(benches, bench_point_add, bench_point_add_g2);
+criterion_main!(benches);
@@ -0,0 +1,81 @@
+use ark_ec::AffineCurve;
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::msm::multi_scalar_mul;
+
+/// Memoizes MSM results, keyed by a SHA-256 hash of the canonical
+/// serialization of their inputs.
+///
+/// Hashing costs a pass over the inputs on every call, which only pays off
+/// when the exact same MSMs are computed over and over, e.g. recomputed
+/// commitments. The cache is never evicted.
+pub struct MsmCache<G: AffineCurve> {
+    results: HashMap<[u8; 32], G::Projective>,
+}
+
+impl<G: AffineCurve> Default for MsmCache<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G: AffineCurve> MsmCache<G> {
+    /// An empty cache.
+    pub fn new() -> Self {
+        Self { results: HashMap::new() }
+    }
+
+    /// Same as [`multi_scalar_mul`], returning the cached result if the same
+    /// inputs were seen before.
+    pub fn msm(&mut self, bases: &[G], scalars: &[<G::ScalarField as PrimeField>::BigInt]) -> G::Projective {
+        let key = input_hash(bases, scalars);
+        *self
+            .results
+            .entry(key)
+            .or_insert_with(|| multi_scalar_mul(bases, scalars))
+    }
+
+    /// Number of distinct MSMs in the cache.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Whether the cache holds no result yet.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+}
+
+/// Hashes the terms the MSM actually uses, i.e. the common prefix of `bases`
+/// and `scalars`, together with their count.
+fn input_hash<G: AffineCurve>(bases: &[G], scalars: &[<G::ScalarField as PrimeField>::BigInt]) -> [u8; 32] {
+    let size = core::cmp::min(bases.len(), scalars.len());
+    let mut buf = Vec::new();
+    (size as u64)
+        .serialize(&mut buf)
+        .expect("serializing into a Vec can't fail");
+    for (base, scalar) in bases[..size].iter().zip(&scalars[..size]) {
+        base.serialize(&mut buf).expect("serializing into a Vec can't fail");
+        scalar.serialize(&mut buf).expect("serializing into a Vec can't fail");
+    }
+    Sha256::digest(&buf).into()
+}
+
+#[test]
+fn test_msm_cache() {
+    use crate::msm::generate_msm_inputs;
+
+    let (point_vec, mut scalar_vec) = generate_msm_inputs(100);
+    let mut cache = MsmCache::new();
+    let res = cache.msm(&point_vec, &scalar_vec);
+    assert_eq!(res, multi_scalar_mul(&point_vec, &scalar_vec));
+    assert_eq!(cache.msm(&point_vec, &scalar_vec), res);
+    assert_eq!(cache.len(), 1);
+
+    scalar_vec.swap(0, 1);
+    assert_eq!(cache.msm(&point_vec, &scalar_vec), multi_scalar_mul(&point_vec, &scalar_vec));
+    assert_eq!(cache.len(), 2);
+}
@@ -0,0 +1,240 @@
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{One, PrimeField, Zero};
+use ark_serialize::CanonicalSerialize;
+
+use crate::msm::{multi_scalar_mul_columns, multi_scalar_mul_fr};
+
+/// Builds the monomial-basis SRS `[g, tau g, tau^2 g, ...]` of `size` powers
+/// from a known `tau`, for tests and examples only: anyone knowing `tau` can
+/// forge openings, so a real SRS comes out of a trusted setup ceremony.
+pub fn generate_srs<G: AffineCurve>(size: usize, tau: G::ScalarField) -> Vec<G> {
+    let g = G::prime_subgroup_generator();
+    let mut power = G::ScalarField::one();
+    let srs: Vec<_> = (0..size)
+        .map(|_| {
+            let p = g.mul(power);
+            power *= tau;
+            p
+        })
+        .collect();
+    G::Projective::batch_normalization_into_affine(&srs)
+}
+
+/// Commits to the polynomial `coeffs[0] + coeffs[1] X + ...` against the
+/// monomial-basis SRS `[g, tau g, tau^2 g, ...]`.
+pub fn commit_polynomial<G: AffineCurve>(srs: &[G], coeffs: &[G::ScalarField]) -> G::Projective {
+    assert!(
+        coeffs.len() <= srs.len(),
+        "degree {} polynomial doesn't fit an SRS of {} powers",
+        coeffs.len().saturating_sub(1),
+        srs.len()
+    );
+    multi_scalar_mul_fr(srs, coeffs)
+}
+
+/// Fiat-Shamir transcript absorbing labeled messages, shaped after Merlin's
+/// `append_message` so that existing transcripts are easy to plug in.
+pub trait Transcript {
+    /// Absorbs `message` under the domain separation `label`.
+    fn append(&mut self, label: &'static [u8], message: &[u8]);
+}
+
+/// Commits to `coeffs` as in [`commit_polynomial`], then absorbs the canonical
+/// serialization of the affine commitment into `transcript` under the
+/// `commitment` label.
+pub fn commit_and_absorb<G: AffineCurve, T: Transcript>(
+    srs: &[G],
+    coeffs: &[G::ScalarField],
+    transcript: &mut T,
+) -> G::Projective {
+    let commitment = commit_polynomial(srs, coeffs);
+    let mut bytes = Vec::new();
+    commitment
+        .into_affine()
+        .serialize(&mut bytes)
+        .expect("serializing into a Vec can't fail");
+    transcript.append(b"commitment", &bytes);
+    commitment
+}
+
+/// Commits to the polynomial taking the values `evals` over the evaluation
+/// domain, against the Lagrange-basis SRS `[L_0(tau) g, L_1(tau) g, ...]`.
+pub fn commit_evaluations<G: AffineCurve>(lagrange_srs: &[G], evals: &[G::ScalarField]) -> G::Projective {
+    assert_eq!(
+        evals.len(),
+        lagrange_srs.len(),
+        "one evaluation is needed per Lagrange basis element"
+    );
+    multi_scalar_mul_fr(lagrange_srs, evals)
+}
+
+/// Commits to every column of the row-major `rows x cols` matrix, each column
+/// holding the coefficients of a polynomial as in [`commit_polynomial`]. The
+/// SRS is walked once per window for all the columns (see
+/// [`multi_scalar_mul_columns`]).
+pub fn commit_matrix_columns<G: AffineCurve>(
+    srs: &[G],
+    matrix: &[G::ScalarField],
+    rows: usize,
+    cols: usize,
+) -> Vec<G::Projective> {
+    assert_eq!(Some(matrix.len()), rows.checked_mul(cols), "the matrix must have rows * cols entries");
+    assert!(rows <= srs.len(), "{} rows don't fit an SRS of {} powers", rows, srs.len());
+
+    let columns: Vec<Vec<_>> = (0..cols)
+        .map(|col| matrix.iter().skip(col).step_by(cols).map(|c| c.into_repr()).collect())
+        .collect();
+    let columns: Vec<&[_]> = columns.iter().map(|column| column.as_slice()).collect();
+    multi_scalar_mul_columns(srs, &columns)
+}
+
+/// Commits to both `coeffs` and the `quotient` polynomial of a KZG-style
+/// opening, as in [`commit_polynomial`].
+///
+/// Both MSMs share a single traversal of the SRS per window (see
+/// [`multi_scalar_mul_columns`]), which is cheaper than two separate MSMs.
+pub fn commit_and_open<G: AffineCurve>(
+    srs: &[G],
+    coeffs: &[G::ScalarField],
+    quotient: &[G::ScalarField],
+) -> (G::Projective, G::Projective) {
+    assert!(
+        coeffs.len() <= srs.len() && quotient.len() <= srs.len(),
+        "polynomials don't fit an SRS of {} powers",
+        srs.len()
+    );
+
+    // Pad the shorter polynomial with zero coefficients so that both columns
+    // span the same SRS prefix.
+    let len = core::cmp::max(coeffs.len(), quotient.len());
+    let to_column = |poly: &[G::ScalarField]| {
+        let mut column: Vec<_> = poly.iter().map(|c| c.into_repr()).collect();
+        column.resize(len, G::ScalarField::zero().into_repr());
+        column
+    };
+    let (coeffs, quotient) = (to_column(coeffs), to_column(quotient));
+
+    let res = multi_scalar_mul_columns(srs, &[&coeffs, &quotient]);
+    (res[0], res[1])
+}
+
+#[test]
+fn test_commit_polynomial() {
+    use ark_bls12_381::{Fr, G1Affine};
+
+    let g = G1Affine::prime_subgroup_generator();
+    let tau = Fr::from(5u64);
+    let srs = vec![
+        g,
+        g.mul(tau.into_repr()).into_affine(),
+        g.mul((tau * tau).into_repr()).into_affine(),
+        g.mul((tau * tau * tau).into_repr()).into_affine(),
+    ];
+
+    // 2 + 3 X + 4 X^2 at X = 5 is 117.
+    let coeffs = [Fr::from(2u64), Fr::from(3u64), Fr::from(4u64)];
+    assert_eq!(commit_polynomial(&srs, &coeffs), g.mul(Fr::from(117u64).into_repr()));
+}
+
+#[test]
+#[should_panic(expected = "doesn't fit")]
+fn test_commit_polynomial_rejects_short_srs() {
+    use ark_bls12_381::{Fr, G1Affine};
+
+    let srs = [G1Affine::prime_subgroup_generator()];
+    commit_polynomial(&srs, &[Fr::from(1u64), Fr::from(2u64)]);
+}
+
+#[test]
+fn test_commit_and_open() {
+    use crate::msm::{generate_msm_inputs, multi_scalar_mul};
+    use ark_bls12_381::Fr;
+    use ark_ff::UniformRand;
+
+    let (srs, _) = generate_msm_inputs(64);
+    let mut rng = ark_std::test_rng();
+    let coeffs: Vec<Fr> = (0..64).map(|_| Fr::rand(&mut rng)).collect();
+    let quotient: Vec<Fr> = (0..63).map(|_| Fr::rand(&mut rng)).collect();
+
+    let to_repr = |poly: &[Fr]| poly.iter().map(|c| c.into_repr()).collect::<Vec<_>>();
+    assert_eq!(
+        commit_and_open(&srs, &coeffs, &quotient),
+        (multi_scalar_mul(&srs, &to_repr(&coeffs)), multi_scalar_mul(&srs, &to_repr(&quotient))),
+    );
+}
+
+#[test]
+fn test_commit_evaluations() {
+    use ark_bls12_381::{Fr, G1Affine};
+
+    // Over the domain {1, 2}, L_0(X) = 2 - X and L_1(X) = X - 1, which are -3
+    // and 4 at tau = 5.
+    let g = G1Affine::prime_subgroup_generator();
+    let lagrange_srs = vec![
+        g.mul((-Fr::from(3u64)).into_repr()).into_affine(),
+        g.mul(Fr::from(4u64).into_repr()).into_affine(),
+    ];
+
+    // The polynomial with values 7 and 11 over {1, 2} is 4 X + 3, i.e. 23 at 5.
+    let evals = [Fr::from(7u64), Fr::from(11u64)];
+    assert_eq!(commit_evaluations(&lagrange_srs, &evals), g.mul(Fr::from(23u64).into_repr()));
+}
+
+#[test]
+fn test_generate_srs() {
+    use ark_bls12_381::{Fr, G1Affine};
+    use ark_ff::Field;
+
+    let tau = Fr::from(5u64);
+    let srs: Vec<G1Affine> = generate_srs(5, tau);
+    assert_eq!(srs.len(), 5);
+    let g = G1Affine::prime_subgroup_generator();
+    for (i, p) in srs.iter().enumerate() {
+        assert_eq!(p.into_projective(), g.mul(tau.pow([i as u64])));
+    }
+}
+
+#[test]
+fn test_commit_matrix_columns() {
+    use ark_bls12_381::{Fr, G1Affine};
+    use ark_ff::UniformRand;
+
+    let (rows, cols) = (20, 3);
+    let srs: Vec<G1Affine> = generate_srs(32, Fr::from(5u64));
+    let mut rng = ark_std::test_rng();
+    let matrix: Vec<Fr> = (0..rows * cols).map(|_| Fr::rand(&mut rng)).collect();
+
+    let expected: Vec<_> = (0..cols)
+        .map(|col| {
+            let column: Vec<Fr> = matrix.iter().skip(col).step_by(cols).copied().collect();
+            commit_polynomial(&srs, &column)
+        })
+        .collect();
+    assert_eq!(commit_matrix_columns(&srs, &matrix, rows, cols), expected);
+}
+
+#[test]
+fn test_commit_and_absorb() {
+    use ark_bls12_381::{Fr, G1Affine};
+
+    #[derive(Default)]
+    struct MockTranscript {
+        messages: Vec<(&'static [u8], Vec<u8>)>,
+    }
+
+    impl Transcript for MockTranscript {
+        fn append(&mut self, label: &'static [u8], message: &[u8]) {
+            self.messages.push((label, message.to_vec()));
+        }
+    }
+
+    let srs: Vec<G1Affine> = generate_srs(4, Fr::from(5u64));
+    let coeffs = [Fr::from(2u64), Fr::from(3u64), Fr::from(4u64)];
+    let mut transcript = MockTranscript::default();
+    let commitment = commit_and_absorb(&srs, &coeffs, &mut transcript);
+    assert_eq!(commitment, commit_polynomial(&srs, &coeffs));
+
+    let mut expected = Vec::new();
+    commitment.into_affine().serialize(&mut expected).unwrap();
+    assert_eq!(transcript.messages, vec![(&b"commitment"[..], expected)]);
+}
@@ -0,0 +1,33 @@
+use core::fmt;
+
+/// Errors reported by the fallible MSM and point addition routines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MsmError {
+    /// A size or index computation doesn't fit in a `usize`, which happens much
+    /// sooner on 32-bit targets such as wasm32.
+    ArithmeticOverflow,
+    /// The denominator at `index` is zero, so the batch can't be inverted.
+    NonInvertibleDenominator { index: usize },
+    /// There are no terms to sum, for callers that treat that as a bug.
+    EmptyInput,
+    /// The window size `c` is outside `1..=max`, so its buckets can't be used.
+    InvalidWindowSize { c: usize, max: usize },
+    /// There is no window `window_index` among the `num_windows` of the scalars.
+    WindowOutOfRange { window_index: usize, num_windows: usize },
+}
+
+impl fmt::Display for MsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MsmError::ArithmeticOverflow => write!(f, "size or index arithmetic overflowed"),
+            MsmError::NonInvertibleDenominator { index } => write!(f, "denominator {} is not invertible", index),
+            MsmError::EmptyInput => write!(f, "no bases or no scalars were given"),
+            MsmError::InvalidWindowSize { c, max } => write!(f, "window size {} is not in 1..={}", c, max),
+            MsmError::WindowOutOfRange { window_index, num_windows } => {
+                write!(f, "window {} is out of range for {} windows", window_index, num_windows)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MsmError {}
@@ -0,0 +1,70 @@
+use ark_bls12_381::{Fr, G1Affine};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{BigInteger256, PrimeField};
+use ark_serialize::CanonicalSerialize;
+
+use crate::msm::{multi_scalar_mul, naive_msm};
+
+/// Upper bound on the number of MSM terms parsed from a fuzz buffer.
+pub const MAX_FUZZ_TERMS: usize = 64;
+
+/// Bytes consumed per term: an 8-byte base seed and a 32-byte scalar.
+const TERM_BYTES: usize = 8 + 32;
+
+/// Differential fuzzing entry point: deterministically parses `data` into a
+/// small BLS12-381 G1 MSM, checks [`multi_scalar_mul`] agrees with
+/// [`naive_msm`] on it, and returns the serialized result.
+///
+/// The first byte picks the number of terms; each term then takes an 8-byte
+/// little-endian seed `k`, giving the base `k * g`, and 32 bytes reduced into
+/// the scalar. Returns `None` when `data` is too short for the terms it asks for.
+pub fn msm_from_bytes(data: &[u8]) -> Option<Vec<u8>> {
+    let (&count, data) = data.split_first()?;
+    let count = count as usize % (MAX_FUZZ_TERMS + 1);
+    if data.len() < count * TERM_BYTES {
+        return None;
+    }
+
+    let g = G1Affine::prime_subgroup_generator();
+    let (bases, scalars): (Vec<_>, Vec<_>) = data
+        .chunks_exact(TERM_BYTES)
+        .take(count)
+        .map(|term| {
+            let (seed, scalar) = term.split_at(8);
+            let seed = u64::from_le_bytes(seed.try_into().unwrap());
+            let base = g.mul(BigInteger256::from(seed));
+            (base, Fr::from_le_bytes_mod_order(scalar).into_repr())
+        })
+        .unzip();
+    let bases = <G1Affine as AffineCurve>::Projective::batch_normalization_into_affine(&bases);
+
+    let res = multi_scalar_mul(&bases, &scalars);
+    assert_eq!(res, naive_msm(&bases, &scalars), "multi_scalar_mul disagrees with naive_msm");
+
+    let mut out = Vec::new();
+    res.into_affine().serialize(&mut out).unwrap();
+    Some(out)
+}
+
+#[test]
+fn test_msm_from_bytes() {
+    assert_eq!(msm_from_bytes(&[]), None);
+    // Asks for two terms but only carries one.
+    assert_eq!(msm_from_bytes(&[2; 1 + TERM_BYTES]), None);
+
+    let buffers: Vec<Vec<u8>> = vec![
+        vec![0],
+        vec![1; 1 + TERM_BYTES],
+        (0..=255u8).cycle().take(1 + 10 * TERM_BYTES).map(|b| b.wrapping_mul(31).wrapping_add(10)).collect(),
+        [&[MAX_FUZZ_TERMS as u8][..], &[0xff; MAX_FUZZ_TERMS * TERM_BYTES]].concat(),
+    ];
+    for buffer in buffers {
+        let out = msm_from_bytes(&buffer).unwrap();
+        assert_eq!(msm_from_bytes(&buffer), Some(out));
+    }
+
+    // The empty MSM is the identity.
+    let mut identity = Vec::new();
+    G1Affine::default().serialize(&mut identity).unwrap();
+    assert_eq!(msm_from_bytes(&[0]), Some(identity));
+}
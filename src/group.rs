@@ -0,0 +1,141 @@
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{BigInteger, PrimeField, Zero};
+
+use crate::msm::{multi_scalar_mul_iter_unfiltered, window_size, OpCount, ReductionMode, SMALL_INPUT_THRESHOLD};
+
+/// The group operations Pippenger's algorithm needs, so that its skeleton
+/// also runs over groups other than arkworks curves, e.g. class groups or toy
+/// groups in tests (see [`multi_scalar_mul_group`]).
+///
+/// `Self` is the type of the bases, added into `Accumulator`s, the type the
+/// buckets and the result are kept in, as affine points and their projective
+/// counterpart are for curves.
+///
+/// The operations are named so as not to clash with `Zero` and `Add`, which
+/// curve points also implement.
+pub trait PippengerGroup: Copy {
+    /// Buckets, window sums and result.
+    type Accumulator: Copy;
+    /// Scalars, whose window digits are extracted with `BigInteger` shifts.
+    type Scalar: BigInteger;
+
+    /// Number of bits of the scalars to cover with windows.
+    fn scalar_bits() -> usize;
+    /// The identity.
+    fn identity() -> Self::Accumulator;
+    /// Whether `acc` is the identity.
+    fn is_identity(acc: &Self::Accumulator) -> bool;
+    /// `acc += other`.
+    fn add_accumulator(acc: &mut Self::Accumulator, other: &Self::Accumulator);
+    /// `acc += acc`.
+    fn double(acc: &mut Self::Accumulator);
+    /// `acc += base`.
+    fn add_mixed(acc: &mut Self::Accumulator, base: &Self);
+    /// Converts accumulators back to bases, for the mixed additions of
+    /// [`ReductionMode::BatchNormalizedMixed`].
+    fn batch_normalize(accs: &[Self::Accumulator]) -> Vec<Self>;
+}
+
+impl<G: AffineCurve> PippengerGroup for G {
+    type Accumulator = G::Projective;
+    type Scalar = <G::ScalarField as PrimeField>::BigInt;
+
+    fn scalar_bits() -> usize {
+        <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize
+    }
+
+    fn identity() -> G::Projective {
+        G::Projective::zero()
+    }
+
+    fn is_identity(acc: &G::Projective) -> bool {
+        acc.is_zero()
+    }
+
+    fn add_accumulator(acc: &mut G::Projective, other: &G::Projective) {
+        *acc += other;
+    }
+
+    fn double(acc: &mut G::Projective) {
+        acc.double_in_place();
+    }
+
+    fn add_mixed(acc: &mut G::Projective, base: &G) {
+        acc.add_assign_mixed(base);
+    }
+
+    fn batch_normalize(accs: &[G::Projective]) -> Vec<G> {
+        G::Projective::batch_normalization_into_affine(accs)
+    }
+}
+
+/// Pippenger's algorithm over any [`PippengerGroup`], running the same bucket
+/// skeleton as [`crate::msm::multi_scalar_mul`]. Curves should still go
+/// through the latter, which adds the single-term shortcut and the identity
+/// base filter on top.
+pub fn multi_scalar_mul_group<G: PippengerGroup>(bases: &[G], scalars: &[G::Scalar]) -> G::Accumulator {
+    let size = core::cmp::min(bases.len(), scalars.len());
+    let c = window_size(size, SMALL_INPUT_THRESHOLD);
+    let scalars_and_bases_iter = scalars[..size].iter().zip(&bases[..size]).filter(|(s, _)| !s.is_zero());
+    let mode = ReductionMode::default();
+    multi_scalar_mul_iter_unfiltered::<G, _, _, _>(scalars_and_bases_iter, c, mode, &mut OpCount::default())
+}
+
+#[test]
+fn test_multi_scalar_mul_group() {
+    use crate::msm::{generate_msm_inputs, multi_scalar_mul};
+    use ark_ff::BigInteger64;
+
+    /// The additive group of the integers modulo a prime.
+    #[derive(Clone, Copy)]
+    struct IntegersMod(u64);
+
+    const N: u64 = 1_000_003;
+
+    impl PippengerGroup for IntegersMod {
+        type Accumulator = u64;
+        type Scalar = BigInteger64;
+
+        fn scalar_bits() -> usize {
+            64
+        }
+
+        fn identity() -> u64 {
+            0
+        }
+
+        fn is_identity(acc: &u64) -> bool {
+            *acc == 0
+        }
+
+        fn add_accumulator(acc: &mut u64, other: &u64) {
+            *acc = (*acc + other) % N;
+        }
+
+        fn double(acc: &mut u64) {
+            *acc = (*acc * 2) % N;
+        }
+
+        fn add_mixed(acc: &mut u64, base: &IntegersMod) {
+            *acc = (*acc + base.0) % N;
+        }
+
+        fn batch_normalize(accs: &[u64]) -> Vec<IntegersMod> {
+            accs.iter().map(|&acc| IntegersMod(acc)).collect()
+        }
+    }
+
+    let bases: Vec<_> = (0..100u64).map(|i| IntegersMod(i * 7919 % N)).collect();
+    let scalars: Vec<u64> = (0..100u64).map(|i| i.wrapping_mul(0x9e37_79b9_7f4a_7c15)).collect();
+    let expected = bases
+        .iter()
+        .zip(&scalars)
+        .fold(0u128, |acc, (b, &s)| (acc + b.0 as u128 * s as u128) % N as u128);
+
+    let scalars: Vec<_> = scalars.into_iter().map(BigInteger64::from).collect();
+    assert_eq!(multi_scalar_mul_group(&bases, &scalars) as u128, expected);
+
+    // Curves go through the blanket implementation.
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    assert_eq!(multi_scalar_mul_group(&point_vec, &scalar_vec), multi_scalar_mul(&point_vec, &scalar_vec));
+}
@@ -1,6 +1,13 @@
-use ark_ec::{AffineCurve, ProjectiveCurve, msm};
-use ark_ff::{PrimeField, UniformRand, Zero};
+use ark_ec::short_weierstrass_jacobian::{GroupAffine, GroupProjective};
+use ark_ec::{AffineCurve, ProjectiveCurve, SWModelParameters};
+use ark_ff::{BigInteger, One, PrimeField, UniformRand, Zero};
 use ark_bls12_381::G1Affine;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use core::borrow::Borrow;
+
+use crate::error::MsmError;
+use crate::group::PippengerGroup;
+use crate::point_add::{batch_affine_sum, is_identity};
 
 
 pub fn generate_msm_inputs(size: usize)
@@ -21,18 +28,3108 @@ pub fn generate_msm_inputs(size: usize)
     (point_vec, scalar_vec)
 }
 
+/// Samples a uniformly random point of the prime-order subgroup, as a random
+/// multiple of the subgroup generator, so that it passes subgroup checks
+/// whatever the cofactor of the curve.
+pub fn random_subgroup_point<G: AffineCurve, R: ark_std::rand::Rng>(rng: &mut R) -> G {
+    G::prime_subgroup_generator().mul(G::ScalarField::rand(rng)).into_affine()
+}
+
+/// Samples `n` points like [`random_subgroup_point`], normalizing them to
+/// affine with a single batched inversion.
+pub fn random_subgroup_points<G: AffineCurve, R: ark_std::rand::Rng>(n: usize, rng: &mut R) -> Vec<G> {
+    let points: Vec<_> = (0..n)
+        .map(|_| G::prime_subgroup_generator().mul(G::ScalarField::rand(rng)))
+        .collect();
+    G::Projective::batch_normalization_into_affine(&points)
+}
+
 /// Currently using Pippenger's algorithm for multi-scalar multiplication (MSM)
 pub fn compute_msm(
     point_vec: Vec<<<G1Affine as AffineCurve>::Projective as ProjectiveCurve>::Affine>,
     scalar_vec: Vec<<<G1Affine as AffineCurve>::ScalarField as PrimeField>::BigInt>,
 ) -> <G1Affine as AffineCurve>::Projective
 {
-    msm::VariableBaseMSM::multi_scalar_mul(point_vec.as_slice(), scalar_vec.as_slice())
+    multi_scalar_mul(point_vec.as_slice(), scalar_vec.as_slice())
 }
 
-#[test]
-fn test() {
-    let size = 1<<14;
-    let (point_vec, scalar_vec) = generate_msm_inputs(size);
-    let res = compute_msm(point_vec, scalar_vec);
-}
\ No newline at end of file
+/// How the buckets of a window are reduced into the window sum.
+///
+/// Which one is faster depends on the inversion-to-multiplication cost ratio
+/// of the base field, so both are kept around to be benchmarked per curve.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReductionMode {
+    /// Running sums over the projective buckets.
+    Projective,
+    /// Batch-normalize the buckets once, then run the sums with mixed additions.
+    BatchNormalizedMixed,
+}
+
+impl Default for ReductionMode {
+    fn default() -> Self {
+        ReductionMode::Projective
+    }
+}
+
+/// Upper estimate of the group operations (additions and doublings) of
+/// [`multi_scalar_mul`] over `size` terms with random scalars: every term adds
+/// into a bucket in every window, each window reduces all its buckets, and the
+/// windows are folded with `c` doublings each.
+pub fn estimate_msm_ops<G: AffineCurve>(size: usize) -> usize {
+    let c = window_size(size, SMALL_INPUT_THRESHOLD);
+    let num_bits = <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+    let num_windows = (num_bits + c - 1) / c;
+
+    let per_window = size + 2 * num_buckets(c) + 1;
+    num_windows * per_window + (num_windows - 1) * (c + 1) + 1
+}
+
+/// Same as [`multi_scalar_mul`], also returning the number of group operations
+/// (additions and doublings) actually performed, to check
+/// [`estimate_msm_ops`] against or compare configurations.
+///
+/// This runs the very same code as [`multi_scalar_mul`], counting every
+/// operation along the way: the doublings and additions of a single-term
+/// scalar multiplication, or else the bucket additions, the bucket reduction
+/// and the final fold of the windows.
+pub fn multi_scalar_mul_counted<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+) -> (G::Projective, usize) {
+    let mut ops = OpCount::enabled();
+    let res = multi_scalar_mul_with_ops(bases, scalars, ReductionMode::default(), &mut ops);
+    (res, ops.total())
+}
+
+/// Group operation counter threaded through the bucket skeleton for
+/// [`multi_scalar_mul_counted`], keeping additions and doublings apart. The
+/// default one counts nothing.
+#[derive(Default)]
+pub(crate) struct OpCount(Option<(usize, usize)>);
+
+impl OpCount {
+    fn enabled() -> Self {
+        OpCount(Some((0, 0)))
+    }
+
+    fn add(&mut self, additions: usize) {
+        if let Some((total, _)) = &mut self.0 {
+            *total += additions;
+        }
+    }
+
+    fn double(&mut self, doublings: usize) {
+        if let Some((_, total)) = &mut self.0 {
+            *total += doublings;
+        }
+    }
+
+    #[cfg(test)]
+    fn additions(&self) -> usize {
+        self.0.map_or(0, |(additions, _)| additions)
+    }
+
+    #[cfg(test)]
+    fn doublings(&self) -> usize {
+        self.0.map_or(0, |(_, doublings)| doublings)
+    }
+
+    fn total(&self) -> usize {
+        self.0.map_or(0, |(additions, doublings)| additions + doublings)
+    }
+}
+
+/// Pippenger's algorithm, adapted from `ark_ec::msm::VariableBaseMSM`.
+///
+/// Buckets and window sums are accumulated in `G::Projective`, which for the
+/// short Weierstrass curves of arkworks is in Jacobian coordinates: `(X, Y, Z)`
+/// stands for the affine point `(X / Z^2, Y / Z^3)`. arkworks doesn't offer
+/// homogeneous projective coordinates for these curves, so there is no switch.
+pub fn multi_scalar_mul<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+) -> G::Projective {
+    multi_scalar_mul_with_reduction(bases, scalars, ReductionMode::default())
+}
+
+/// Same as [`multi_scalar_mul`], failing with [`MsmError::EmptyInput`] when
+/// there are no terms, i.e. no bases or no scalars, rather than returning the
+/// identity.
+pub fn multi_scalar_mul_nonempty<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+) -> Result<G::Projective, MsmError> {
+    if core::cmp::min(bases.len(), scalars.len()) == 0 {
+        return Err(MsmError::EmptyInput);
+    }
+    Ok(multi_scalar_mul(bases, scalars))
+}
+
+/// Reference MSM doing one scalar multiplication per term.
+pub fn naive_msm<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+) -> G::Projective {
+    bases.iter().zip(scalars).map(|(base, scalar)| base.mul(*scalar)).sum()
+}
+
+/// Computes every prefix MSM, `out[k] = MSM(bases[..=k], scalars[..=k])`,
+/// with a running accumulator.
+///
+/// This takes one scalar multiplication per term, like [`naive_msm`], rather
+/// than the bucket method: all `n` prefixes cost about as much as the single
+/// naive MSM of `n` terms, which beats `n` separate [`multi_scalar_mul`]s but
+/// loses to one of them when only the last prefix is needed.
+pub fn multi_scalar_mul_prefix<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+) -> Vec<G::Projective> {
+    let mut acc = G::Projective::zero();
+    bases
+        .iter()
+        .zip(scalars)
+        .map(|(base, scalar)| {
+            if !scalar.is_zero() {
+                acc += &base.mul(*scalar);
+            }
+            acc
+        })
+        .collect()
+}
+
+/// Same as [`multi_scalar_mul`], with the scalars given as field elements.
+pub fn multi_scalar_mul_fr<G: AffineCurve>(bases: &[G], scalars: &[G::ScalarField]) -> G::Projective {
+    multi_scalar_mul(bases, &batch_into_repr(scalars))
+}
+
+/// Converts every field element out of Montgomery form, as `into_repr` does.
+///
+/// Each conversion is a Montgomery reduction of its own element, with no work
+/// to share across elements (unlike inversions), so this can only spread the
+/// elements over the rayon thread pool with the `parallel` feature.
+pub fn batch_into_repr<F: PrimeField>(fs: &[F]) -> Vec<F::BigInt> {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        fs.par_iter().map(|f| f.into_repr()).collect()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    fs.iter().map(|f| f.into_repr()).collect()
+}
+
+/// Same as [`multi_scalar_mul_fr`], converting the scalars out of Montgomery
+/// form into `repr_buf` rather than a fresh allocation, so that a tight loop
+/// of MSMs reuses the same buffer.
+///
+/// The window digits can't be read off the Montgomery form `s * R mod p`
+/// directly, so each scalar is still converted exactly once, up front, rather
+/// than once per window. See the `bench_montgomery_conversion` benchmark for
+/// the cost of that conversion relative to the MSM.
+pub fn multi_scalar_mul_montgomery<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[G::ScalarField],
+    repr_buf: &mut Vec<<G::ScalarField as PrimeField>::BigInt>,
+) -> G::Projective {
+    repr_buf.clear();
+    repr_buf.extend(scalars.iter().map(|s| s.into_repr()));
+    multi_scalar_mul(bases, repr_buf)
+}
+
+/// Same as [`multi_scalar_mul`], with an explicit bucket [`ReductionMode`].
+pub fn multi_scalar_mul_with_reduction<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    mode: ReductionMode,
+) -> G::Projective {
+    multi_scalar_mul_with_ops(bases, scalars, mode, &mut OpCount::default())
+}
+
+/// Same as [`multi_scalar_mul_with_reduction`], counting the group operations
+/// into `ops`.
+fn multi_scalar_mul_with_ops<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    mode: ReductionMode,
+    ops: &mut OpCount,
+) -> G::Projective {
+    let size = core::cmp::min(bases.len(), scalars.len());
+    // A single term is just a scalar multiplication, without any windows or
+    // buckets to set up.
+    if size == 1 {
+        return double_and_add(&bases[0], &scalars[0], ops);
+    }
+    let c = window_size(size, SMALL_INPUT_THRESHOLD);
+    multi_scalar_mul_iter_counted::<G, _, _, _>(scalars[..size].iter().zip(&bases[..size]), c, mode, ops)
+}
+
+/// `scalar * base` by double-and-add from the top set bit down, as
+/// `AffineCurve::mul` does, counting every doubling and addition into `ops`.
+fn double_and_add<G: AffineCurve>(
+    base: &G,
+    scalar: &<G::ScalarField as PrimeField>::BigInt,
+    ops: &mut OpCount,
+) -> G::Projective {
+    let mut res = G::Projective::zero();
+    for i in (0..scalar.num_bits() as usize).rev() {
+        res.double_in_place();
+        ops.double(1);
+        if scalar.get_bit(i) {
+            res.add_assign_mixed(base);
+            ops.add(1);
+        }
+    }
+    res
+}
+
+/// Same as [`multi_scalar_mul`], with inputs shorter than `threshold` using
+/// the fixed small window instead of [`SMALL_INPUT_THRESHOLD`].
+pub fn multi_scalar_mul_with_threshold<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    threshold: usize,
+) -> G::Projective {
+    let size = core::cmp::min(bases.len(), scalars.len());
+    let c = window_size(size, threshold);
+    multi_scalar_mul_iter::<G, _, _, _>(scalars[..size].iter().zip(&bases[..size]), c, ReductionMode::default())
+}
+
+/// Same as [`multi_scalar_mul`], without the up-front pass filtering out zero
+/// scalars, which only pays off for sparse inputs. See the `bench_zero_filter`
+/// benchmark for the crossover.
+pub fn multi_scalar_mul_nofilter<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+) -> G::Projective {
+    let size = core::cmp::min(bases.len(), scalars.len());
+    let c = window_size(size, SMALL_INPUT_THRESHOLD);
+    multi_scalar_mul_iter_unfiltered::<G, _, _, _>(
+        scalars[..size].iter().zip(&bases[..size]),
+        c,
+        ReductionMode::default(),
+        &mut OpCount::default(),
+    )
+}
+
+/// Partial MSM over `bases[range]` and `scalars[range]` only, for sharding an
+/// MSM across provers: the partials over a partition of the indices sum to the
+/// whole MSM.
+pub fn multi_scalar_mul_partial<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    range: core::ops::Range<usize>,
+) -> G::Projective {
+    assert!(
+        range.start <= range.end && range.end <= core::cmp::min(bases.len(), scalars.len()),
+        "range {:?} out of bounds for {} terms",
+        range,
+        core::cmp::min(bases.len(), scalars.len())
+    );
+    multi_scalar_mul(&bases[range.clone()], &scalars[range])
+}
+
+/// MSM over bases known to be `generator * exponents[i]`: the exponents are
+/// folded into the scalars, leaving the single scalar multiplication
+/// `generator * sum(scalars[i] * exponents[i])`.
+pub fn multi_scalar_mul_generator_powers<G: AffineCurve>(
+    generator: G,
+    exponents: &[G::ScalarField],
+    scalars: &[G::ScalarField],
+) -> G::Projective {
+    assert_eq!(exponents.len(), scalars.len(), "exponent and scalar vectors must have the same length");
+
+    let folded: G::ScalarField = exponents.iter().zip(scalars).map(|(e, s)| *e * s).sum();
+    generator.mul(folded.into_repr())
+}
+
+/// Same as [`multi_scalar_mul`], over `(base, scalar)` pairs rather than two
+/// parallel slices, borrowing from the pairs in place.
+pub fn multi_scalar_mul_tuples<G: AffineCurve>(pairs: &[(G, <G::ScalarField as PrimeField>::BigInt)]) -> G::Projective {
+    let c = window_size(pairs.len(), SMALL_INPUT_THRESHOLD);
+    let scalars_and_bases = pairs.iter().map(|(base, scalar)| (scalar, base));
+    multi_scalar_mul_iter::<G, _, _, _>(scalars_and_bases, c, ReductionMode::default())
+}
+
+/// Same as [`multi_scalar_mul`], consuming the scalars in a single pass as
+/// they are produced, so that they never need to be buffered: the `i`-th
+/// scalar pairs with `bases[i]`, and the buckets of all the windows are kept
+/// at once. Scalars past `bases.len()` are ignored.
+///
+/// The window size is picked from `bases.len()`, as the number of scalars is
+/// not known up front.
+pub fn multi_scalar_mul_scalar_stream<G: AffineCurve>(
+    bases: &[G],
+    scalars: impl Iterator<Item = <G::ScalarField as PrimeField>::BigInt>,
+) -> G::Projective {
+    let c = window_size(bases.len(), SMALL_INPUT_THRESHOLD);
+    let num_bits = <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+    let num_windows = (num_bits + c - 1) / c;
+    let fr_one = G::ScalarField::one().into_repr();
+
+    let mut unit_sum = G::Projective::zero();
+    let mut reducers: Vec<_> = (0..num_windows)
+        .map(|_| WindowReducer::<G>::new(c).expect("the heuristic window size is valid"))
+        .collect();
+    for (scalar, base) in scalars.zip(bases).filter(|(s, b)| !s.is_zero() && !is_identity(*b)) {
+        if scalar == fr_one {
+            unit_sum.add_assign_mixed(base);
+            continue;
+        }
+        for (w, reducer) in reducers.iter_mut().enumerate() {
+            reducer.add(window_digit(&scalar, w * c, c), base);
+        }
+    }
+
+    let mut window_sums: Vec<_> = reducers.iter_mut().map(|reducer| reducer.current_sum()).collect();
+    window_sums[0] += &unit_sum;
+    combine_window_sums::<G>(&window_sums, c)
+}
+
+/// Computes `MSM(bases, scalars_a) - MSM(bases, scalars_b)` as the single
+/// MSM of the scalar differences `a_i - b_i`, computed in the field. Equal
+/// scalars cancel out before costing anything, and two equal vectors give the
+/// identity right away.
+///
+/// Like [`multi_scalar_mul`], the inputs are truncated to the shortest of the
+/// three vectors. Scalars needn't be reduced: they are taken modulo the group
+/// order.
+pub fn multi_scalar_mul_diff<G: AffineCurve>(
+    bases: &[G],
+    scalars_a: &[<G::ScalarField as PrimeField>::BigInt],
+    scalars_b: &[<G::ScalarField as PrimeField>::BigInt],
+) -> G::Projective {
+    let size = core::cmp::min(bases.len(), core::cmp::min(scalars_a.len(), scalars_b.len()));
+    let (scalars_a, scalars_b) = (&scalars_a[..size], &scalars_b[..size]);
+    if scalars_a == scalars_b {
+        return G::Projective::zero();
+    }
+
+    let to_field = |s: &<G::ScalarField as PrimeField>::BigInt| G::ScalarField::from_le_bytes_mod_order(&s.to_bytes_le());
+    let diffs: Vec<_> = scalars_a
+        .iter()
+        .zip(scalars_b)
+        .map(|(a, b)| (to_field(a) - to_field(b)).into_repr())
+        .collect();
+    multi_scalar_mul(&bases[..size], &diffs)
+}
+
+/// Same as [`multi_scalar_mul`], treating the scalars as zero-padded to the
+/// length of `bases`: `bases[i]` always pairs with `scalars[i]`, and the bases
+/// past `scalars.len()` contribute nothing to the result. This never panics
+/// on mismatched lengths; scalars past `bases.len()` are ignored likewise.
+pub fn multi_scalar_mul_zeropad<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+) -> G::Projective {
+    multi_scalar_mul(bases, scalars)
+}
+
+/// Same as [`multi_scalar_mul`], with an explicit window size `c` instead of
+/// the logarithmic heuristic. Fails with [`MsmError::InvalidWindowSize`] unless
+/// `c` is in `1..=min(MODULUS_BITS, MAX_BUCKET_WINDOW_BITS)`.
+pub fn multi_scalar_mul_with_window_size<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    c: usize,
+) -> Result<G::Projective, MsmError> {
+    check_window_size::<G::ScalarField>(c)?;
+
+    let size = core::cmp::min(bases.len(), scalars.len());
+    let scalars_and_bases_iter = scalars[..size].iter().zip(&bases[..size]);
+    Ok(multi_scalar_mul_iter::<G, _, _, _>(scalars_and_bases_iter, c, ReductionMode::default()))
+}
+
+/// Picks the window size running fastest on this machine for MSMs of about
+/// `sample_size` terms, by timing MSMs over random inputs for the window sizes
+/// around [`multi_scalar_mul`]'s heuristic.
+///
+/// The random inputs are drawn from `rng`. Timings vary from run to run, so
+/// the result is not deterministic; it is cached per curve and per power of
+/// two of `sample_size`, so that only the first call for a given size pays
+/// for the calibration.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn calibrate_window_size<G: AffineCurve, R: ark_std::rand::Rng>(sample_size: usize, rng: &mut R) -> usize {
+    use std::any::TypeId;
+    use std::collections::HashMap;
+    use std::sync::{Mutex, OnceLock};
+    use std::time::{Duration, Instant};
+
+    static CACHE: OnceLock<Mutex<HashMap<(TypeId, u32), usize>>> = OnceLock::new();
+    let key = (TypeId::of::<G>(), ark_std::log2(sample_size));
+    if let Some(&c) = CACHE.get_or_init(Default::default).lock().unwrap().get(&key) {
+        return c;
+    }
+
+    let bases: Vec<_> = (0..sample_size).map(|_| G::Projective::rand(rng)).collect();
+    let bases = G::Projective::batch_normalization_into_affine(&bases);
+    let scalars: Vec<_> = (0..sample_size)
+        .map(|_| G::ScalarField::rand(rng).into_repr())
+        .collect();
+
+    let num_bits = <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+    let heuristic = window_size(sample_size, SMALL_INPUT_THRESHOLD);
+    let max = core::cmp::min(num_bits, MAX_BUCKET_WINDOW_BITS);
+    let candidates = heuristic.saturating_sub(2).max(1)..=(heuristic + 2).min(max);
+
+    const REPS: u32 = 3;
+    let mut best = (Duration::MAX, heuristic);
+    for c in candidates {
+        let start = Instant::now();
+        for _ in 0..REPS {
+            multi_scalar_mul_with_window_size(&bases, &scalars, c).expect("candidates are valid window sizes");
+        }
+        best = best.min((start.elapsed(), c));
+    }
+
+    CACHE.get_or_init(Default::default).lock().unwrap().insert(key, best.1);
+    best.1
+}
+
+/// Median timings of the MSM backends over the same inputs, as measured by
+/// [`compare_backends`].
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug)]
+pub struct BackendTimings {
+    /// [`naive_msm`].
+    pub naive: std::time::Duration,
+    /// [`multi_scalar_mul`].
+    pub pippenger: std::time::Duration,
+    /// [`MsmEngine::with_thread_pool`], on a pool as large as the global one.
+    #[cfg(feature = "parallel")]
+    pub parallel: std::time::Duration,
+}
+
+/// Times every MSM backend `reps` times over `bases` and `scalars` on this
+/// machine, to help pick one, after checking that they all agree.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn compare_backends<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    reps: usize,
+) -> BackendTimings {
+    use std::time::{Duration, Instant};
+
+    assert!(reps > 0, "at least one repetition is needed");
+    let expected = multi_scalar_mul(bases, scalars);
+    assert_eq!(naive_msm(bases, scalars), expected, "naive_msm disagrees with multi_scalar_mul");
+    #[cfg(feature = "parallel")]
+    let mut engine = MsmEngine::with_thread_pool(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(rayon::current_num_threads())
+            .build()
+            .expect("failed to build the MSM thread pool"),
+    );
+    #[cfg(feature = "parallel")]
+    assert_eq!(engine.msm(bases, scalars), expected, "the parallel MsmEngine disagrees with multi_scalar_mul");
+
+    let median = |msm: &mut dyn FnMut() -> G::Projective| -> Duration {
+        let mut timings: Vec<_> = (0..reps)
+            .map(|_| {
+                let start = Instant::now();
+                msm();
+                start.elapsed()
+            })
+            .collect();
+        timings.sort();
+        timings[reps / 2]
+    };
+
+    BackendTimings {
+        naive: median(&mut || naive_msm(bases, scalars)),
+        pippenger: median(&mut || multi_scalar_mul(bases, scalars)),
+        #[cfg(feature = "parallel")]
+        parallel: median(&mut || engine.msm(bases, scalars)),
+    }
+}
+
+/// Computes `-MSM(bases, scalars)`.
+///
+/// Negating the projective result only negates its `y` coordinate, a single
+/// field negation with no normalization involved, which is cheaper than
+/// negating all the bases up front.
+pub fn multi_scalar_mul_neg<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+) -> G::Projective {
+    -multi_scalar_mul(bases, scalars)
+}
+
+/// MSM over scalars given as little-endian `u64` limb arrays, as some hosts
+/// hand them over, rather than as arkworks `BigInt`s. The window digits are
+/// read straight out of the limbs, without converting the scalars first.
+///
+/// `N` must match the limb count of the scalar field's `BigInt`.
+pub fn multi_scalar_mul_u64_limbs<G: AffineCurve, const N: usize>(
+    bases: &[G],
+    scalars: &[[u64; N]],
+) -> G::Projective {
+    assert_eq!(
+        N,
+        <<G::ScalarField as PrimeField>::BigInt as BigInteger>::NUM_LIMBS,
+        "scalars must have one u64 per BigInt limb"
+    );
+
+    let size = core::cmp::min(bases.len(), scalars.len());
+    let c = window_size(size, SMALL_INPUT_THRESHOLD);
+    multi_scalar_mul_iter::<G, _, _, _>(scalars[..size].iter().zip(&bases[..size]), c, ReductionMode::default())
+}
+
+/// Same as [`multi_scalar_mul`], polling `should_cancel` before each window
+/// and giving up with `None` as soon as it returns `true`.
+pub fn multi_scalar_mul_cancellable<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    should_cancel: &dyn Fn() -> bool,
+) -> Option<G::Projective> {
+    let size = core::cmp::min(bases.len(), scalars.len());
+    let c = window_size(size, SMALL_INPUT_THRESHOLD);
+    let scalars_and_bases_iter = scalars[..size].iter().zip(&bases[..size]).filter(|(s, _)| !s.is_zero());
+
+    let num_bits = <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+    let mut window_sums = Vec::new();
+    for w_start in (0..num_bits).step_by(c) {
+        if should_cancel() {
+            return None;
+        }
+        window_sums.push(window_sum::<G, _, _, _>(scalars_and_bases_iter.clone(), w_start, c, ReductionMode::default()));
+    }
+
+    Some(combine_window_sums::<G>(&window_sums, c))
+}
+
+/// Same as [`multi_scalar_mul`], stopping as soon as the partial result equals
+/// `target`. Returns the result so far, and whether it matched `target`.
+///
+/// The windows are folded highest first, as in [`combine_window_sums`], each
+/// one computed only once the fold reaches it. Once the fold is down to window
+/// `k`, the partial result is `sum_{w >= k} window_sum_w * 2^(c * (w - k))`,
+/// i.e. the MSM of the scalars shifted right by `c * k` bits, and that is what
+/// `target` is checked against. The check after the lowest window is against
+/// the full MSM.
+pub fn multi_scalar_mul_until<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    target: &G::Projective,
+) -> (G::Projective, bool) {
+    let size = core::cmp::min(bases.len(), scalars.len());
+    let c = window_size(size, SMALL_INPUT_THRESHOLD);
+    let scalars_and_bases_iter = scalars[..size].iter().zip(&bases[..size]).filter(|(s, _)| !s.is_zero());
+
+    let num_bits = <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+    let mut res = G::Projective::zero();
+    for w_start in (0..num_bits).step_by(c).rev() {
+        for _ in 0..c {
+            res.double_in_place();
+        }
+        res += &window_sum::<G, _, _, _>(scalars_and_bases_iter.clone(), w_start, c, ReductionMode::default());
+        if res == *target {
+            return (res, true);
+        }
+    }
+    (res, false)
+}
+
+/// MSM over 16-bit scalars, e.g. quantized weights, as a single window of
+/// `c = 16` whose digits are the scalars themselves: no digit extraction, no
+/// higher windows and no doublings.
+///
+/// The 2^16 - 1 buckets still take ~2^17 additions to reduce, so this only
+/// beats [`multi_scalar_mul`] from several thousand terms on.
+pub fn multi_scalar_mul_packed16<G: AffineCurve>(bases: &[G], scalars: &[u16]) -> G::Projective {
+    let mut buckets = vec![G::Projective::zero(); num_buckets(16)];
+    for (&scalar, base) in scalars.iter().zip(bases) {
+        if scalar != 0 {
+            buckets[(scalar - 1) as usize].add_assign_mixed(base);
+        }
+    }
+    reduce_buckets::<G>(&buckets, ReductionMode::default(), &mut OpCount::default())
+}
+
+/// MSM over scalars known to fit in `bits <= 64` bits, e.g. quantized values
+/// or counters: only the windows covering those `bits` are processed, with
+/// digits taken straight off the `u64`s. Generalizes
+/// [`multi_scalar_mul_bits`] and [`multi_scalar_mul_packed16`].
+pub fn multi_scalar_mul_small<G: AffineCurve>(bases: &[G], scalars: &[u64], bits: usize) -> G::Projective {
+    assert!(bits > 0 && bits <= 64, "small scalars must have 1..=64 bits");
+    debug_assert!(
+        bits == 64 || scalars.iter().all(|&s| s >> bits == 0),
+        "scalar doesn't fit in {} bits",
+        bits
+    );
+
+    let size = core::cmp::min(bases.len(), scalars.len());
+    let c = core::cmp::min(window_size(size, SMALL_INPUT_THRESHOLD), bits);
+    let mask = u64::MAX >> (64 - c);
+
+    let zero = G::Projective::zero();
+    let window_sums: Vec<_> = (0..bits)
+        .step_by(c)
+        .map(|w_start| {
+            let mut buckets = vec![zero; num_buckets(c)];
+            for (&scalar, base) in scalars[..size].iter().zip(&bases[..size]) {
+                let digit = (scalar >> w_start) & mask;
+                if digit != 0 {
+                    buckets[(digit - 1) as usize].add_assign_mixed(base);
+                }
+            }
+            reduce_buckets::<G>(&buckets, ReductionMode::default(), &mut OpCount::default())
+        })
+        .collect();
+    combine_window_sums::<G>(&window_sums, c)
+}
+
+/// MSM over a sparse scalar vector, given as `(index, scalar)` entries naming
+/// the base each nonzero scalar applies to.
+pub fn multi_scalar_mul_sparse<G: AffineCurve>(
+    bases: &[G],
+    entries: &[(usize, <G::ScalarField as PrimeField>::BigInt)],
+) -> G::Projective {
+    if let Some((index, _)) = entries.iter().find(|(index, _)| *index >= bases.len()) {
+        panic!("entry index {} out of range for {} bases", index, bases.len());
+    }
+
+    let c = window_size(entries.len(), SMALL_INPUT_THRESHOLD);
+    let scalars_and_bases = entries.iter().map(|(index, scalar)| (scalar, &bases[*index]));
+    multi_scalar_mul_iter::<G, _, _, _>(scalars_and_bases, c, ReductionMode::default())
+}
+
+/// MSM over signed scalars given as magnitudes and sign flags, i.e.
+/// `sum_i (-1)^signs[i] * magnitudes[i] * bases[i]`, by negating the bases of
+/// the negative terms.
+pub fn multi_scalar_mul_signed_input<G: AffineCurve>(
+    bases: &[G],
+    magnitudes: &[<G::ScalarField as PrimeField>::BigInt],
+    signs: &[bool],
+) -> G::Projective {
+    assert_eq!(magnitudes.len(), signs.len(), "one sign is needed per magnitude");
+
+    let size = core::cmp::min(bases.len(), magnitudes.len());
+    let mut bases = bases[..size].to_vec();
+    conditionally_negate_bases(&mut bases, &signs[..size]);
+    multi_scalar_mul(&bases, &magnitudes[..size])
+}
+
+/// MSM against the periodic base sequence `bases[0], ..., bases[period - 1],
+/// bases[0], ...`, repeated to cover all the scalars.
+///
+/// The scalars of each base position are summed up first, shrinking the
+/// problem to an MSM of `period` terms. Like the sums, unreduced scalars are
+/// taken modulo the group order.
+pub fn multi_scalar_mul_periodic<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    period: usize,
+) -> G::Projective {
+    assert!(period > 0, "period must be positive");
+    assert_eq!(bases.len(), period, "one base is needed per position of the period");
+    assert_eq!(scalars.len() % period, 0, "scalars must cover a whole number of periods");
+
+    let mut folded = vec![G::ScalarField::zero(); period];
+    for chunk in scalars.chunks(period) {
+        for (acc, s) in folded.iter_mut().zip(chunk) {
+            *acc += G::ScalarField::from_le_bytes_mod_order(&s.to_bytes_le());
+        }
+    }
+    multi_scalar_mul_fr(bases, &folded)
+}
+
+/// MSM over the terms at the indices `offset, offset + stride, ...` of
+/// `bases` and `scalars`, e.g. a coset of an FFT domain, walked in place
+/// rather than copied out.
+pub fn multi_scalar_mul_coset<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    stride: usize,
+    offset: usize,
+) -> G::Projective {
+    assert!(stride > 0, "stride must be positive");
+    let size = core::cmp::min(bases.len(), scalars.len());
+    assert!(offset < size, "offset {} out of bounds for {} terms", offset, size);
+
+    let c = window_size((size - offset + stride - 1) / stride, SMALL_INPUT_THRESHOLD);
+    let scalars_and_bases = scalars[offset..size].iter().zip(&bases[offset..size]).step_by(stride);
+    multi_scalar_mul_iter::<G, _, _, _>(scalars_and_bases, c, ReductionMode::default())
+}
+
+/// Same as [`multi_scalar_mul`], with the scalars split into signed `c`-bit
+/// digits (see [`signed_window_digits`]) and negative digits adding the negated
+/// base. A window then only needs `2^(c - 1)` buckets, halving the cost of the
+/// bucket reduction; the number of nonzero digits per scalar stays about the
+/// same, with at most one more from the extra carry window. See
+/// [`multi_scalar_mul_naf`] for a recoding with fewer nonzero digits.
+pub fn multi_scalar_mul_signed_digits<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+) -> G::Projective {
+    let size = core::cmp::min(bases.len(), scalars.len());
+    let c = window_size(size, SMALL_INPUT_THRESHOLD);
+    let num_bits = <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+    let digits: Vec<_> = scalars[..size].iter().map(|s| signed_window_digits(s, c, num_bits)).collect();
+
+    let zero = G::Projective::zero();
+    let window_sums: Vec<_> = (0..num_bits / c + 1)
+        .map(|w| {
+            // Signed digits are at most 2^(c - 1) in absolute value.
+            let mut buckets = vec![zero; num_buckets(c - 1) + 1];
+            for (digits, base) in digits.iter().zip(&bases[..size]) {
+                let digit = digits[w];
+                if digit > 0 {
+                    buckets[(digit - 1) as usize].add_assign_mixed(base);
+                } else if digit < 0 {
+                    buckets[(-digit - 1) as usize].add_assign_mixed(&-*base);
+                }
+            }
+            reduce_buckets::<G>(&buckets, ReductionMode::default(), &mut OpCount::default())
+        })
+        .collect();
+    combine_window_sums::<G>(&window_sums, c)
+}
+
+/// Splits the `num_bits`-bit `scalar` into `num_bits / c + 1` signed `c`-bit
+/// digits, lowest first, with `scalar = sum_w digits[w] * 2^(c * w)`.
+///
+/// Every digit but the last is in `[-2^(c - 1), 2^(c - 1))`, borrowing from the
+/// next window when the unsigned digit is too large. The last window has less
+/// than `c` bits of the scalar left, so absorbing the final carry keeps it in
+/// `[0, 2^(c - 1)]`.
+pub fn signed_window_digits<B: BigInteger>(scalar: &B, c: usize, num_bits: usize) -> Vec<i64> {
+    assert!(c > 0 && c < 63, "window size must be in 1..63");
+
+    let num_windows = num_bits / c + 1;
+    let mut carry = 0;
+    let mut digits: Vec<i64> = (0..num_windows)
+        .map(|w| {
+            let digit = window_digit(scalar, w * c, c) as i64 + carry;
+            if digit >= 1 << (c - 1) {
+                carry = 1;
+                digit - (1 << c)
+            } else {
+                carry = 0;
+                digit
+            }
+        })
+        .collect();
+    // Undo the borrow of the last window, whose digit then fits the buckets.
+    if carry == 1 {
+        digits[num_windows - 1] += 1 << c;
+    }
+    digits
+}
+
+/// Same as [`multi_scalar_mul`], with the scalars recoded into their width-`c`
+/// NAF (see [`wnaf_digits`]) and negative digits adding the negated base. A
+/// scalar then has about `num_bits / (c + 1)` nonzero digits, each costing one
+/// bucket addition, against up to `num_bits / c` nonzero `c`-bit windows.
+///
+/// NAF digits don't line up on `c`-bit windows, so every bit position gets its
+/// own window, with one bucket per odd digit magnitude, and the window sums are
+/// folded with a single doubling each.
+pub fn multi_scalar_mul_naf<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+) -> G::Projective {
+    let size = core::cmp::min(bases.len(), scalars.len());
+    let c = naf_width(size);
+    let digits: Vec<_> = scalars[..size].iter().map(|s| wnaf_digits(s, c)).collect();
+    let num_positions = digits.iter().map(Vec::len).max().unwrap_or(0);
+
+    let zero = G::Projective::zero();
+    let window_sums: Vec<_> = (0..num_positions)
+        .map(|i| {
+            // The digit `d` goes to bucket `(|d| - 1) / 2`, for the odd
+            // magnitudes below 2^(c - 1).
+            let mut buckets = vec![zero; num_buckets(c - 1) / 2 + 1];
+            for (digits, base) in digits.iter().zip(&bases[..size]) {
+                match digits.get(i) {
+                    Some(&digit) if digit > 0 => buckets[(digit / 2) as usize].add_assign_mixed(base),
+                    Some(&digit) if digit < 0 => buckets[(-digit / 2) as usize].add_assign_mixed(&-*base),
+                    _ => {}
+                }
+            }
+            // sum_j (2j + 1) * bucket[j] = 2 * sum_j (j + 1) * bucket[j] - sum_j bucket[j]
+            let total = buckets.iter().fold(zero, |total, bucket| total + bucket);
+            reduce_buckets::<G>(&buckets, ReductionMode::default(), &mut OpCount::default()).double() - total
+        })
+        .collect();
+    combine_window_sums::<G>(&window_sums, 1)
+}
+
+/// NAF width used by [`multi_scalar_mul_naf`] for `size` terms: the usual
+/// window size, but at least 2, below which the NAF has no zeros to gain.
+fn naf_width(size: usize) -> usize {
+    core::cmp::max(window_size(size, SMALL_INPUT_THRESHOLD), 2)
+}
+
+/// Recodes `scalar` into its width-`c` non-adjacent form, lowest bit first,
+/// with `scalar = sum_i digits[i] * 2^i`.
+///
+/// Every nonzero digit is odd and in `(-2^(c - 1), 2^(c - 1))`, and is followed
+/// by at least `c - 1` zero digits. The recoding is at most one digit longer
+/// than the scalar, plus trailing zeros.
+pub fn wnaf_digits<B: BigInteger>(scalar: &B, c: usize) -> Vec<i64> {
+    assert!(c >= 2 && c < 63, "NAF width must be in 2..63");
+
+    let limbs = scalar.as_ref();
+    let num_bits = scalar.num_bits() as usize;
+    let mut digits = Vec::with_capacity(num_bits + c);
+    // `carry` is the 1 borrowed by the last negative digit, owed to the bit
+    // at the current position.
+    let mut carry = 0;
+    while digits.len() < num_bits || carry != 0 {
+        let window = limbs_window_digit(limbs, digits.len(), c) as i64 + carry;
+        if window & 1 == 0 {
+            // An even window keeps the carry: the bit and the carry are either
+            // both 0, or both 1 and carry into the next bit.
+            digits.push(0);
+            continue;
+        }
+
+        let digit = if window < 1 << (c - 1) {
+            carry = 0;
+            window
+        } else {
+            carry = 1;
+            window - (1 << c)
+        };
+        digits.push(digit);
+        digits.extend(core::iter::repeat(0).take(c - 1));
+    }
+    digits
+}
+
+/// MSMs of several scalar vectors against the same bases. Each window walks
+/// the bases once for all the vectors, keeping one set of buckets per vector,
+/// which saves base memory traffic over separate MSMs.
+pub fn multi_scalar_mul_columns<G: AffineCurve>(
+    bases: &[G],
+    columns: &[&[<G::ScalarField as PrimeField>::BigInt]],
+) -> Vec<G::Projective> {
+    let size = columns.iter().fold(bases.len(), |size, column| core::cmp::min(size, column.len()));
+    let c = window_size(size, SMALL_INPUT_THRESHOLD);
+    let num_bits = <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+    let fr_one = G::ScalarField::one().into_repr();
+
+    let zero = G::Projective::zero();
+    let mut buckets = vec![vec![zero; num_buckets(c)]; columns.len()];
+    let mut window_sums = vec![Vec::new(); columns.len()];
+    for w_start in (0..num_bits).step_by(c) {
+        let mut res = vec![zero; columns.len()];
+        buckets.iter_mut().for_each(|buckets| buckets.iter_mut().for_each(|b| *b = zero));
+
+        for (i, base) in bases[..size].iter().enumerate() {
+            for (k, column) in columns.iter().enumerate() {
+                let scalar = &column[i];
+                if *scalar == fr_one {
+                    // We only process unit scalars once in the first window.
+                    if w_start == 0 {
+                        res[k].add_assign_mixed(base);
+                    }
+                } else {
+                    let digit = window_digit(scalar, w_start, c);
+                    if digit != 0 {
+                        buckets[k][(digit - 1) as usize].add_assign_mixed(base);
+                    }
+                }
+            }
+        }
+
+        for (k, res) in res.into_iter().enumerate() {
+            let sum = reduce_buckets::<G>(&buckets[k], ReductionMode::default(), &mut OpCount::default());
+            window_sums[k].push(res + sum);
+        }
+    }
+
+    window_sums
+        .iter()
+        .map(|window_sums| combine_window_sums::<G>(window_sums, c))
+        .collect()
+}
+
+/// MSMs of two scalar vectors against the same bases, e.g. two challenge
+/// powers per base, walking the bases once per window for both of them (see
+/// [`multi_scalar_mul_columns`]).
+pub fn multi_scalar_mul_dual<G: AffineCurve>(
+    bases: &[G],
+    scalars_a: &[<G::ScalarField as PrimeField>::BigInt],
+    scalars_b: &[<G::ScalarField as PrimeField>::BigInt],
+) -> (G::Projective, G::Projective) {
+    let res = multi_scalar_mul_columns(bases, &[scalars_a, scalars_b]);
+    (res[0], res[1])
+}
+
+/// MSM of a single column of the row-major `scalars` matrix, which has one row
+/// of `cols` scalars per base, striding through the matrix instead of copying
+/// the column out.
+pub fn multi_scalar_mul_matrix_col<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    cols: usize,
+    col: usize,
+) -> G::Projective {
+    assert!(col < cols, "column {} out of range for {} columns", col, cols);
+    assert_eq!(Some(scalars.len()), bases.len().checked_mul(cols), "the matrix must have one row per base");
+
+    let column = scalars.iter().skip(col).step_by(cols);
+    let c = window_size(bases.len(), SMALL_INPUT_THRESHOLD);
+    multi_scalar_mul_iter::<G, _, _, _>(column.zip(bases), c, ReductionMode::default())
+}
+
+/// Same as [`multi_scalar_mul`], with the bases stored transposed relative to
+/// the scalars: the scalars are the row-major entries of a matrix with
+/// `row_stride` entries per row, and `bases` holds the matching bases in
+/// column-major order, so that the scalar at `(row, col)` pairs with
+/// `bases[col * rows + row]`. The bases are read in place with that stride,
+/// rather than gathered into the natural order first.
+pub fn multi_scalar_mul_transposed<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    row_stride: usize,
+) -> G::Projective {
+    assert!(row_stride > 0, "row stride must be positive");
+    assert_eq!(bases.len(), scalars.len(), "the matrix must have one base per scalar");
+    assert_eq!(scalars.len() % row_stride, 0, "the matrix must have whole rows");
+
+    let rows = scalars.len() / row_stride;
+    let scalars_and_bases = scalars
+        .iter()
+        .enumerate()
+        .map(|(i, scalar)| (scalar, &bases[(i % row_stride) * rows + i / row_stride]));
+    let c = window_size(scalars.len(), SMALL_INPUT_THRESHOLD);
+    multi_scalar_mul_iter::<G, _, _, _>(scalars_and_bases, c, ReductionMode::default())
+}
+
+/// MSM over short Weierstrass bases stored as a struct of arrays: the `i`-th
+/// base is `(xs[i], ys[i])`, or the point at infinity if `infinity[i]` is set.
+///
+/// Bases are rebuilt from their coordinates as the windows walk over them.
+pub fn multi_scalar_mul_soa<P: SWModelParameters>(
+    xs: &[P::BaseField],
+    ys: &[P::BaseField],
+    infinity: &[bool],
+    scalars: &[<P::ScalarField as PrimeField>::BigInt],
+) -> GroupProjective<P> {
+    assert!(
+        xs.len() == ys.len() && ys.len() == infinity.len(),
+        "coordinate and infinity arrays must have the same length"
+    );
+
+    let size = core::cmp::min(xs.len(), scalars.len());
+    let bases = xs[..size]
+        .iter()
+        .zip(&ys[..size])
+        .zip(&infinity[..size])
+        .map(|((&x, &y), &infinity)| GroupAffine::<P>::new(x, y, infinity));
+    let c = window_size(size, SMALL_INPUT_THRESHOLD);
+    multi_scalar_mul_iter::<GroupAffine<P>, _, _, _>(scalars[..size].iter().zip(bases), c, ReductionMode::default())
+}
+
+/// Same as [`multi_scalar_mul`], returning the raw Jacobian coordinates
+/// `(X, Y, Z)` of the result, without normalizing it, for further arithmetic
+/// outside of arkworks. The identity is always returned as `(1, 1, 0)`.
+pub fn multi_scalar_mul_jacobian<P: SWModelParameters>(
+    bases: &[GroupAffine<P>],
+    scalars: &[<P::ScalarField as PrimeField>::BigInt],
+) -> (P::BaseField, P::BaseField, P::BaseField) {
+    let res = multi_scalar_mul(bases, scalars);
+    if res.is_zero() {
+        return (P::BaseField::one(), P::BaseField::one(), P::BaseField::zero());
+    }
+    (res.x, res.y, res.z)
+}
+
+/// Same as [`multi_scalar_mul`], also checking once at the end that the
+/// result is on the curve, to catch a regression in the bucket or reduction
+/// arithmetic at runtime. Panics if it isn't.
+#[cfg(feature = "validate-outputs")]
+pub fn multi_scalar_mul_validated<P: SWModelParameters>(
+    bases: &[GroupAffine<P>],
+    scalars: &[<P::ScalarField as PrimeField>::BigInt],
+) -> GroupProjective<P> {
+    let res = multi_scalar_mul(bases, scalars);
+    assert_msm_output_on_curve(&res);
+    res
+}
+
+/// Panics unless the MSM result `res` is on the curve.
+#[cfg(feature = "validate-outputs")]
+fn assert_msm_output_on_curve<P: SWModelParameters>(res: &GroupProjective<P>) {
+    assert!(res.into_affine().is_on_curve(), "MSM produced a point off the curve");
+}
+
+/// Same as [`multi_scalar_mul`], returning only the affine `x` coordinate of
+/// the result, or `None` for the identity. Only `x = X / Z^2` is normalized,
+/// which saves the multiplications computing `y`.
+pub fn multi_scalar_mul_x<P: SWModelParameters>(
+    bases: &[GroupAffine<P>],
+    scalars: &[<P::ScalarField as PrimeField>::BigInt],
+) -> Option<P::BaseField> {
+    use ark_ff::Field;
+
+    let res = multi_scalar_mul(bases, scalars);
+    if res.is_zero() {
+        return None;
+    }
+    let z_inv = res.z.inverse().expect("the result is not the identity");
+    Some(res.x * z_inv.square())
+}
+
+/// MSM over single-bit scalars, e.g. circuit selectors: sums the `bases[i]`
+/// whose `bits[i]` is set with [`batch_affine_sum`], without any windowing.
+pub fn multi_scalar_mul_bits<P: SWModelParameters>(bases: &[GroupAffine<P>], bits: &[bool]) -> GroupProjective<P> {
+    let selected: Vec<_> = bases
+        .iter()
+        .zip(bits)
+        .filter(|(_, &bit)| bit)
+        .map(|(base, _)| *base)
+        .collect();
+    batch_affine_sum(&selected)
+}
+
+/// Reusable MSM state for proving sessions running many MSMs: the bucket and
+/// window storage is kept across calls, and the windows can be spread over a
+/// dedicated thread pool with the `parallel` feature.
+pub struct MsmEngine<G: AffineCurve> {
+    #[cfg(feature = "parallel")]
+    pool: Option<rayon::ThreadPool>,
+    buckets: Vec<G::Projective>,
+    window_sums: Vec<G::Projective>,
+}
+
+impl<G: AffineCurve> Default for MsmEngine<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G: AffineCurve> MsmEngine<G> {
+    /// An engine running every MSM on the calling thread.
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "parallel")]
+            pool: None,
+            buckets: Vec::new(),
+            window_sums: Vec::new(),
+        }
+    }
+
+    /// An engine processing the windows of every MSM in parallel on `pool`.
+    /// Each window then needs its own buckets, so only the window storage is
+    /// reused.
+    #[cfg(feature = "parallel")]
+    pub fn with_thread_pool(pool: rayon::ThreadPool) -> Self {
+        Self {
+            pool: Some(pool),
+            ..Self::new()
+        }
+    }
+
+    /// Same as [`multi_scalar_mul`].
+    pub fn msm(
+        &mut self,
+        bases: &[G],
+        scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    ) -> G::Projective {
+        let size = core::cmp::min(bases.len(), scalars.len());
+        let c = window_size(size, SMALL_INPUT_THRESHOLD);
+        let scalars_and_bases_iter = scalars[..size].iter().zip(&bases[..size]).filter(|(s, _)| !s.is_zero());
+        let num_bits = <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+        let mode = ReductionMode::default();
+
+        self.window_sums.clear();
+
+        #[cfg(feature = "parallel")]
+        if let Some(pool) = &self.pool {
+            use rayon::prelude::*;
+
+            let window_starts: Vec<_> = (0..num_bits).step_by(c).collect();
+            pool.install(|| {
+                window_starts
+                    .into_par_iter()
+                    .map(|w_start| window_sum::<G, _, _, _>(scalars_and_bases_iter.clone(), w_start, c, mode))
+                    .collect_into_vec(&mut self.window_sums)
+            });
+            return combine_window_sums::<G>(&self.window_sums, c);
+        }
+
+        for w_start in (0..num_bits).step_by(c) {
+            let sum = window_sum_with_buckets::<G, _, _, _>(
+                scalars_and_bases_iter.clone(),
+                w_start,
+                c,
+                mode,
+                &mut self.buckets,
+                &mut OpCount::default(),
+            );
+            self.window_sums.push(sum);
+        }
+        combine_window_sums::<G>(&self.window_sums, c)
+    }
+}
+
+/// The buckets of a single `c`-bit window, filled one `(digit, base)` term at a
+/// time, with the window sum available at any point in between.
+///
+/// The bucket reduction is only rerun by [`WindowReducer::current_sum`] when
+/// terms were added since it last ran; otherwise its result is reused.
+pub struct WindowReducer<G: AffineCurve> {
+    buckets: Vec<G::Projective>,
+    sum: Option<G::Projective>,
+}
+
+impl<G: AffineCurve> WindowReducer<G> {
+    /// Empty buckets for `c`-bit digits, failing with
+    /// [`MsmError::InvalidWindowSize`] as [`multi_scalar_mul_with_window_size`] does.
+    pub fn new(c: usize) -> Result<Self, MsmError> {
+        check_window_size::<G::ScalarField>(c)?;
+        Ok(Self {
+            buckets: vec![G::Projective::zero(); num_buckets(c)],
+            sum: Some(G::Projective::zero()),
+        })
+    }
+
+    /// Adds `digit * base` to the window. Panics if `digit` doesn't fit in
+    /// the window.
+    pub fn add(&mut self, digit: u64, base: &G) {
+        if digit == 0 {
+            return;
+        }
+        assert!(digit as usize <= self.buckets.len(), "digit {} doesn't fit in the window", digit);
+        self.buckets[(digit - 1) as usize].add_assign_mixed(base);
+        self.sum = None;
+    }
+
+    /// The sum of `digit * base` over the terms added so far.
+    pub fn current_sum(&mut self) -> G::Projective {
+        let buckets = &self.buckets;
+        *self
+            .sum
+            .get_or_insert_with(|| reduce_buckets::<G>(buckets, ReductionMode::default(), &mut OpCount::default()))
+    }
+}
+
+/// Precomputed tables of fixed bases, for running many MSMs against the same
+/// bases (e.g. an SRS).
+///
+/// For every base and window `w`, the table holds `base * 2^(c * w)`, so that
+/// [`MsmPrecomp::eval`] accumulates the digits of all the windows into a single
+/// set of buckets, without the doublings between windows. This takes
+/// `num_windows` times the memory of the bases.
+pub struct MsmPrecomp<G: AffineCurve> {
+    c: usize,
+    num_windows: usize,
+    /// `tables[i * num_windows + w]` is `bases[i] * 2^(c * w)`.
+    tables: Vec<G>,
+}
+
+impl<G: AffineCurve> MsmPrecomp<G> {
+    /// Precomputes the tables of `bases`, with the window size
+    /// [`multi_scalar_mul`] would pick for them.
+    pub fn new(bases: &[G]) -> Self {
+        let c = window_size(bases.len(), SMALL_INPUT_THRESHOLD);
+        let num_bits = <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+        let mut precomp = Self {
+            c,
+            num_windows: (num_bits + c - 1) / c,
+            tables: Vec::new(),
+        };
+        precomp.extend(bases);
+        precomp
+    }
+
+    /// Appends the tables of `new_bases`, which then pair with the scalars
+    /// following those of the bases already in the table. The window size is
+    /// kept from [`MsmPrecomp::new`].
+    pub fn extend(&mut self, new_bases: &[G]) {
+        let mut tables = Vec::with_capacity(new_bases.len() * self.num_windows);
+        for base in new_bases {
+            let mut shifted = base.into_projective();
+            for _ in 0..self.num_windows {
+                tables.push(shifted);
+                for _ in 0..self.c {
+                    shifted.double_in_place();
+                }
+            }
+        }
+        self.tables.extend(G::Projective::batch_normalization_into_affine(&tables));
+    }
+
+    /// Number of bases in the table.
+    pub fn len(&self) -> usize {
+        self.tables.len() / self.num_windows
+    }
+
+    /// Whether the table holds no bases.
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// Size in bits of the windows the table was built for.
+    pub fn window_bits(&self) -> usize {
+        self.c
+    }
+
+    /// Writes the table, after a header of its window bits and base count, so
+    /// that it can be restored with [`MsmPrecomp::deserialize`] rather than
+    /// recomputed. The points are written uncompressed, trading size for
+    /// loading speed.
+    pub fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        (self.c as u64).serialize(&mut writer)?;
+        (self.len() as u64).serialize(&mut writer)?;
+        for p in &self.tables {
+            p.serialize_uncompressed(&mut writer)?;
+        }
+        Ok(())
+    }
+
+    /// Restores a table written by [`MsmPrecomp::serialize`], failing if it
+    /// was built for other than `window_bits`-bit windows, or for a window size
+    /// [`multi_scalar_mul_with_window_size`] would reject.
+    pub fn deserialize<R: Read>(mut reader: R, window_bits: usize) -> Result<Self, SerializationError> {
+        let c = u64::deserialize(&mut reader)?;
+        if c != window_bits as u64 || check_window_size::<G::ScalarField>(window_bits).is_err() {
+            return Err(SerializationError::InvalidData);
+        }
+        let len = usize::try_from(u64::deserialize(&mut reader)?).map_err(|_| SerializationError::InvalidData)?;
+
+        let num_bits = <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+        let num_windows = (num_bits + window_bits - 1) / window_bits;
+        let num_points = len.checked_mul(num_windows).ok_or(SerializationError::InvalidData)?;
+        let tables = (0..num_points)
+            .map(|_| G::deserialize_uncompressed(&mut reader))
+            .collect::<Result<_, _>>()?;
+        let precomp = Self {
+            c: window_bits,
+            num_windows,
+            tables,
+        };
+        if precomp.len() != len {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(precomp)
+    }
+
+    /// Same as [`multi_scalar_mul`] over the bases of the table.
+    pub fn eval(&self, scalars: &[<G::ScalarField as PrimeField>::BigInt]) -> G::Projective {
+        let mut buckets = vec![G::Projective::zero(); num_buckets(self.c)];
+        for (scalar, tables) in scalars.iter().zip(self.tables.chunks_exact(self.num_windows)) {
+            if scalar.is_zero() {
+                continue;
+            }
+            for (w, table) in tables.iter().enumerate() {
+                let digit = window_digit(scalar, w * self.c, self.c);
+                if digit != 0 {
+                    buckets[(digit - 1) as usize].add_assign_mixed(table);
+                }
+            }
+        }
+        reduce_buckets::<G>(&buckets, ReductionMode::default(), &mut OpCount::default())
+    }
+}
+
+/// An MSM kept up to date as its scalars change one at a time.
+///
+/// The window sums are kept, so that changing a scalar only touches the
+/// windows where its digit changes, each by a single small multiple of the
+/// base, before the windows are folded again.
+pub struct MsmSingleUpdate<G: AffineCurve> {
+    bases: Vec<G>,
+    scalars: Vec<<G::ScalarField as PrimeField>::BigInt>,
+    c: usize,
+    window_sums: Vec<G::Projective>,
+}
+
+impl<G: AffineCurve> MsmSingleUpdate<G> {
+    /// Runs the MSM of `bases` and `scalars`, keeping its window sums.
+    pub fn new(bases: &[G], scalars: &[<G::ScalarField as PrimeField>::BigInt]) -> Self {
+        let size = core::cmp::min(bases.len(), scalars.len());
+        let c = window_size(size, SMALL_INPUT_THRESHOLD);
+        Self {
+            bases: bases[..size].to_vec(),
+            scalars: scalars[..size].to_vec(),
+            c,
+            window_sums: compute_window_sums(&bases[..size], &scalars[..size], c)
+                .expect("the heuristic window size is valid"),
+        }
+    }
+
+    /// The current MSM result.
+    pub fn result(&self) -> G::Projective {
+        combine_window_sums::<G>(&self.window_sums, self.c)
+    }
+
+    /// Replaces the scalar at `index` with `scalar`, and returns the updated
+    /// result.
+    pub fn update(&mut self, index: usize, scalar: <G::ScalarField as PrimeField>::BigInt) -> G::Projective {
+        assert!(index < self.scalars.len(), "index {} out of bounds", index);
+
+        let old = core::mem::replace(&mut self.scalars[index], scalar);
+        let base = self.bases[index];
+        for (w, sum) in self.window_sums.iter_mut().enumerate() {
+            let old_digit = window_digit(&old, w * self.c, self.c);
+            let new_digit = window_digit(&scalar, w * self.c, self.c);
+            if new_digit > old_digit {
+                *sum += &base.mul(<G::ScalarField as PrimeField>::BigInt::from(new_digit - old_digit));
+            } else if new_digit < old_digit {
+                *sum -= &base.mul(<G::ScalarField as PrimeField>::BigInt::from(old_digit - new_digit));
+            }
+        }
+        self.result()
+    }
+}
+
+/// Same as [`multi_scalar_mul`], allocating the buckets and window sums from
+/// `arena` rather than the global allocator, which can be slow on wasm32. The
+/// arena can be reset between calls to reuse its memory.
+#[cfg(feature = "arena")]
+pub fn multi_scalar_mul_in_arena<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    arena: &bumpalo::Bump,
+) -> G::Projective {
+    let size = core::cmp::min(bases.len(), scalars.len());
+    let c = window_size(size, SMALL_INPUT_THRESHOLD);
+    let scalars_and_bases_iter = scalars[..size].iter().zip(&bases[..size]).filter(|(s, _)| !s.is_zero());
+    let num_bits = <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+    let mode = ReductionMode::default();
+
+    let buckets = arena.alloc_slice_fill_copy(num_buckets(c), G::Projective::zero());
+    let mut window_sums = bumpalo::collections::Vec::with_capacity_in((num_bits + c - 1) / c, arena);
+    for w_start in (0..num_bits).step_by(c) {
+        window_sums.push(window_sum_into_buckets::<G, _, _, _>(
+            scalars_and_bases_iter.clone(),
+            w_start,
+            c,
+            mode,
+            buckets,
+            &mut OpCount::default(),
+        ));
+    }
+    combine_window_sums::<G>(&window_sums, c)
+}
+
+/// Same as [`multi_scalar_mul`], splitting the work into (window, base range)
+/// tiles of `tile_len` terms on the rayon thread pool, whose work stealing
+/// then balances skewed inputs: with mostly small scalars, the low windows
+/// hold most of the nonzero digits, and spreading only whole windows over the
+/// threads (as [`MsmEngine::with_thread_pool`] does) leaves most of them idle.
+///
+/// Each tile reduces its own buckets, and window sums being linear in the
+/// terms, the tile sums of a window just add up. That reduction costs ~2^(c+1)
+/// additions per tile, so tiles should be several times `2^c` terms long;
+/// [`default_tile_len`] picks such a length.
+#[cfg(feature = "parallel")]
+pub fn multi_scalar_mul_tiled<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    tile_len: usize,
+) -> G::Projective {
+    use rayon::prelude::*;
+
+    assert!(tile_len > 0, "tiles must hold at least one term");
+    let size = core::cmp::min(bases.len(), scalars.len());
+    if size == 0 {
+        return G::Projective::zero();
+    }
+    let c = window_size(size, SMALL_INPUT_THRESHOLD);
+    let num_bits = <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+    let num_windows = (num_bits + c - 1) / c;
+    let num_tiles = (size + tile_len - 1) / tile_len;
+
+    let tile_sums: Vec<_> = (0..num_windows * num_tiles)
+        .into_par_iter()
+        .map(|t| {
+            let (w, tile) = (t / num_tiles, t % num_tiles);
+            let range = tile * tile_len..core::cmp::min((tile + 1) * tile_len, size);
+            let scalars_and_bases_iter = scalars[range.clone()]
+                .iter()
+                .zip(&bases[range])
+                .filter(|(s, b)| !s.is_zero() && !is_identity(*b));
+            window_sum::<G, _, _, _>(scalars_and_bases_iter, w * c, c, ReductionMode::default())
+        })
+        .collect();
+
+    let window_sums: Vec<G::Projective> = tile_sums.chunks(num_tiles).map(|tiles| tiles.iter().sum()).collect();
+    combine_window_sums::<G>(&window_sums, c)
+}
+
+/// A tile length for [`multi_scalar_mul_tiled`] over `size` terms: four times
+/// the `2^c` buckets of its windows, so that reducing the buckets of a tile
+/// costs about half as much as filling them.
+#[cfg(feature = "parallel")]
+pub fn default_tile_len(size: usize) -> usize {
+    4 << window_size(size, SMALL_INPUT_THRESHOLD)
+}
+
+/// Inputs shorter than this use a fixed 3-bit window rather than one sized by
+/// [`window_size`]'s logarithmic heuristic.
+pub const SMALL_INPUT_THRESHOLD: usize = 32;
+
+/// Picks the window size `c` for an MSM of `size` terms.
+///
+/// This stays below `log2(size) + 2`, so the `2^c - 1` buckets of a window
+/// always fit in a `usize`, even on wasm32.
+pub(crate) fn window_size(size: usize, threshold: usize) -> usize {
+    if size < threshold {
+        3
+    } else {
+        ln_without_floats(size) + 2
+    }
+}
+
+/// Runs Pippenger's algorithm with `c`-bit windows over `(scalar, base)` pairs.
+/// The iterator is walked once per window, so it should be cheap to clone.
+///
+/// Bases are usually borrowed from a slice, but may also be built on the fly.
+/// Scalars are read as little-endian `u64` limbs, so they may be `BigInt`s as
+/// well as raw limb arrays.
+///
+/// Terms with a zero scalar or an identity base contribute nothing, so they
+/// are filtered out before they cost any bucket addition.
+fn multi_scalar_mul_iter<G, S, B, I>(scalars_and_bases: I, c: usize, mode: ReductionMode) -> G::Projective
+where
+    G: AffineCurve,
+    B: Borrow<G>,
+    S: AsRef<[u64]>,
+    I: Iterator<Item = (S, B)> + Clone,
+{
+    multi_scalar_mul_iter_counted::<G, _, _, _>(scalars_and_bases, c, mode, &mut OpCount::default())
+}
+
+/// Same as [`multi_scalar_mul_iter`], counting the group operations into `ops`.
+fn multi_scalar_mul_iter_counted<G, S, B, I>(
+    scalars_and_bases: I,
+    c: usize,
+    mode: ReductionMode,
+    ops: &mut OpCount,
+) -> G::Projective
+where
+    G: AffineCurve,
+    B: Borrow<G>,
+    S: AsRef<[u64]>,
+    I: Iterator<Item = (S, B)> + Clone,
+{
+    let scalars_and_bases_iter =
+        scalars_and_bases.filter(|(s, b)| !limbs_are_zero(s.as_ref()) && !is_identity::<G>(b.borrow()));
+    multi_scalar_mul_iter_unfiltered::<G, _, _, _>(scalars_and_bases_iter, c, mode, ops)
+}
+
+/// Same as [`multi_scalar_mul_iter`], without filtering out zero scalars first.
+/// Their digits are all zero, so they still never touch a bucket.
+///
+/// This is the skeleton of every bucket MSM, generic over the
+/// [`PippengerGroup`] operations so that [`multi_scalar_mul_group`] shares it.
+///
+/// [`multi_scalar_mul_group`]: crate::group::multi_scalar_mul_group
+pub(crate) fn multi_scalar_mul_iter_unfiltered<G, S, B, I>(
+    scalars_and_bases_iter: I,
+    c: usize,
+    mode: ReductionMode,
+    ops: &mut OpCount,
+) -> G::Accumulator
+where
+    G: PippengerGroup,
+    B: Borrow<G>,
+    S: AsRef<[u64]>,
+    I: Iterator<Item = (S, B)> + Clone,
+{
+    let window_starts: Vec<_> = (0..G::scalar_bits()).step_by(c).collect();
+
+    // Each window is of size `c`.
+    // We divide up the bits 0..num_bits into windows of size `c`, and
+    // process each such window.
+    let window_sums = window_sums_at::<G, _, _, _>(scalars_and_bases_iter, &window_starts, c, mode, ops);
+
+    fold_window_sums::<G>(&window_sums, c, ops)
+}
+
+/// Computes the sum of each `c`-bit window starting at one of `window_starts`.
+fn window_sums_at<G, S, B, I>(
+    scalars_and_bases_iter: I,
+    window_starts: &[usize],
+    c: usize,
+    mode: ReductionMode,
+    ops: &mut OpCount,
+) -> Vec<G::Accumulator>
+where
+    G: PippengerGroup,
+    B: Borrow<G>,
+    S: AsRef<[u64]>,
+    I: Iterator<Item = (S, B)> + Clone,
+{
+    // A single window walks the iterator only once, so there's nothing to
+    // clone it for.
+    if let [w_start] = *window_starts {
+        return vec![window_sum_with_buckets::<G, _, _, _>(
+            scalars_and_bases_iter,
+            w_start,
+            c,
+            mode,
+            &mut Vec::new(),
+            ops,
+        )];
+    }
+
+    window_starts
+        .iter()
+        // This clone is cheap, because the iterator contains just a
+        // pointer and an index into the original vectors.
+        .map(|&w_start| {
+            let scalars_and_bases_iter = scalars_and_bases_iter.clone();
+            window_sum_with_buckets::<G, _, _, _>(scalars_and_bases_iter, w_start, c, mode, &mut Vec::new(), ops)
+        })
+        .collect()
+}
+
+/// Computes the sum of the `c`-bit window starting at bit `w_start`, that is
+/// `sum_i digit_i * base_i` where `digit_i` are those bits of `scalar_i`.
+fn window_sum<G, S, B, I>(scalars_and_bases_iter: I, w_start: usize, c: usize, mode: ReductionMode) -> G::Accumulator
+where
+    G: PippengerGroup,
+    B: Borrow<G>,
+    S: AsRef<[u64]>,
+    I: Iterator<Item = (S, B)>,
+{
+    let mut ops = OpCount::default();
+    window_sum_with_buckets::<G, _, _, _>(scalars_and_bases_iter, w_start, c, mode, &mut Vec::new(), &mut ops)
+}
+
+/// Same as [`window_sum`], using `buckets` as scratch storage.
+fn window_sum_with_buckets<G, S, B, I>(
+    scalars_and_bases_iter: I,
+    w_start: usize,
+    c: usize,
+    mode: ReductionMode,
+    buckets: &mut Vec<G::Accumulator>,
+    ops: &mut OpCount,
+) -> G::Accumulator
+where
+    G: PippengerGroup,
+    B: Borrow<G>,
+    S: AsRef<[u64]>,
+    I: Iterator<Item = (S, B)>,
+{
+    // We don't need the "zero" bucket, so we only have 2^c - 1 buckets.
+    // Stale buckets are zeroed by `window_sum_into_buckets`.
+    buckets.resize(num_buckets(c), G::identity());
+    window_sum_into_buckets::<G, _, _, _>(scalars_and_bases_iter, w_start, c, mode, buckets, ops)
+}
+
+/// Same as [`window_sum_with_buckets`], with `buckets` holding exactly the
+/// `2^c - 1` buckets of the window, whatever their previous contents.
+fn window_sum_into_buckets<G, S, B, I>(
+    scalars_and_bases_iter: I,
+    w_start: usize,
+    c: usize,
+    mode: ReductionMode,
+    buckets: &mut [G::Accumulator],
+    ops: &mut OpCount,
+) -> G::Accumulator
+where
+    G: PippengerGroup,
+    B: Borrow<G>,
+    S: AsRef<[u64]>,
+    I: Iterator<Item = (S, B)>,
+{
+    debug_assert_eq!(Ok(buckets.len()), checked_num_buckets(c));
+
+    let mut res = G::identity();
+    buckets.iter_mut().for_each(|b| *b = G::identity());
+    scalars_and_bases_iter.for_each(|(scalar, base)| {
+        let scalar = scalar.as_ref();
+        if limbs_are_one(scalar) {
+            // We only process unit scalars once in the first window.
+            if w_start == 0 {
+                G::add_mixed(&mut res, base.borrow());
+                ops.add(1);
+            }
+        } else {
+            let scalar = limbs_window_digit(scalar, w_start, c);
+
+            // If the scalar is non-zero, we update the corresponding
+            // bucket.
+            // (Recall that `buckets` doesn't have a zero bucket.)
+            if scalar != 0 {
+                G::add_mixed(&mut buckets[(scalar - 1) as usize], base.borrow());
+                ops.add(1);
+            }
+        }
+    });
+
+    G::add_accumulator(&mut res, &reduce_buckets::<G>(buckets, mode, ops));
+    ops.add(1);
+    res
+}
+
+/// Largest window size [`window_digit`] supports: digits are returned as a
+/// `u64`. Bucket methods stop well short of it, at
+/// [`MAX_BUCKET_WINDOW_BITS`]; wider digits are read with [`window_digit_wide`].
+pub const MAX_WINDOW_BITS: usize = 64;
+
+/// Largest window size the bucket methods accept. A `c`-bit window takes
+/// `2^c - 1` buckets, so at 24 bits they already weigh 2.4 GB for BLS12-381
+/// G1, and are only worth it for inputs far larger than that.
+pub const MAX_BUCKET_WINDOW_BITS: usize = 24;
+
+/// Checks that `c` is a window size the bucket methods can run with, i.e. in
+/// `1..=min(MODULUS_BITS, MAX_BUCKET_WINDOW_BITS)` with buckets that can be
+/// counted in a `usize`.
+fn check_window_size<F: PrimeField>(c: usize) -> Result<(), MsmError> {
+    let num_bits = F::Params::MODULUS_BITS as usize;
+    let max = core::cmp::min(num_bits, MAX_BUCKET_WINDOW_BITS);
+    if c == 0 || c > max {
+        return Err(MsmError::InvalidWindowSize { c, max });
+    }
+    checked_num_buckets(c).map(|_| ())
+}
+
+/// Extracts the `c`-bit digit of `scalar` starting at bit `w_start`, for `c`
+/// up to [`MAX_WINDOW_BITS`].
+pub(crate) fn window_digit<B: BigInteger>(scalar: &B, w_start: usize, c: usize) -> u64 {
+    limbs_window_digit(scalar.as_ref(), w_start, c)
+}
+
+/// Same as [`window_digit`], reading the digit straight out of the
+/// little-endian `u64` limbs of the scalar. A window within the limb holding
+/// bit `w_start` is read from it alone; one crossing into the next limb goes
+/// through [`limbs_window_digit_wide`]. Bits past the last limb are zero.
+fn limbs_window_digit(limbs: &[u64], w_start: usize, c: usize) -> u64 {
+    debug_assert!(c > 0 && c <= MAX_WINDOW_BITS);
+    let (limb, bit) = (w_start / 64, w_start % 64);
+    if bit + c > 64 {
+        return limbs_window_digit_wide(limbs, w_start, c) as u64;
+    }
+
+    // We mod the remaining bits by 2^{window size}, thus taking `c` bits.
+    // The mask is built by shifting down rather than up, so that `c = 64`
+    // doesn't overflow.
+    (limbs.get(limb).copied().unwrap_or(0) >> bit) & (u64::MAX >> (64 - c))
+}
+
+/// Same as [`limbs_window_digit`], for `c` up to 128: the digit then lies in
+/// up to three limbs, starting with the one holding bit `w_start`.
+fn limbs_window_digit_wide(limbs: &[u64], w_start: usize, c: usize) -> u128 {
+    debug_assert!(c > 0 && c <= 2 * MAX_WINDOW_BITS);
+    let limb_at = |i: usize| limbs.get(i).copied().unwrap_or(0) as u128;
+    let (limb, bit) = (w_start / 64, w_start % 64);
+
+    let mut digit = (limb_at(limb) | (limb_at(limb + 1) << 64)) >> bit;
+    if bit + c > 128 {
+        digit |= limb_at(limb + 2) << (128 - bit);
+    }
+    digit & (u128::MAX >> (128 - c))
+}
+
+/// Whether the little-endian limbs hold zero.
+fn limbs_are_zero(limbs: &[u64]) -> bool {
+    limbs.iter().all(|&limb| limb == 0)
+}
+
+/// Whether the little-endian limbs hold one.
+fn limbs_are_one(limbs: &[u64]) -> bool {
+    limbs.split_first().map_or(false, |(&lo, hi)| lo == 1 && limbs_are_zero(hi))
+}
+
+/// Same as [`window_digit`], for `c` up to 128. The MSM digit reads go
+/// through the same code whenever their window crosses a limb boundary.
+pub fn window_digit_wide<B: BigInteger>(scalar: &B, w_start: usize, c: usize) -> u128 {
+    assert!(c > 0 && c <= 2 * MAX_WINDOW_BITS, "window size must be in 1..=128, got {}", c);
+    limbs_window_digit_wide(scalar.as_ref(), w_start, c)
+}
+
+/// Lays out the `c`-bit window digits of all the scalars as a flat buffer of
+/// shape `[num_windows][scalars.len()]`, ready to be copied to a GPU: the digit
+/// of `scalars[i]` in window `w` is at `w * scalars.len() + i`. Returns the
+/// buffer and `num_windows`.
+pub fn scalar_digit_matrix<F: PrimeField>(scalars: &[F::BigInt], c: usize) -> (Vec<u32>, usize) {
+    assert!(c > 0 && c <= 32, "window digits must fit in a u32, i.e. c in 1..=32");
+
+    let num_bits = F::Params::MODULUS_BITS as usize;
+    let num_windows = (num_bits + c - 1) / c;
+    let mut digits = Vec::with_capacity(num_windows * scalars.len());
+    for w_start in (0..num_bits).step_by(c) {
+        digits.extend(scalars.iter().map(|s| window_digit(s, w_start, c) as u32));
+    }
+    (digits, num_windows)
+}
+
+/// Diagnostic returning the indices of the (at most) `top_k` terms with the
+/// largest scalars, largest first, as a cheap proxy for which bases weigh the
+/// most on the result, e.g. to debug an unexpectedly large commitment. The
+/// scalars are compared as integers, i.e. by bit length first; ties keep the
+/// lowest index first.
+pub fn dominant_terms<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    top_k: usize,
+) -> Vec<usize> {
+    let size = core::cmp::min(bases.len(), scalars.len());
+    let mut indices: Vec<usize> = (0..size).collect();
+    indices.sort_by(|&i, &j| scalars[j].cmp(&scalars[i]));
+    indices.truncate(top_k);
+    indices
+}
+
+/// Diagnostic counting, for the `window_index`-th window of `c` bits, how many
+/// scalars land in each bucket: `histogram[d - 1]` is the number of scalars
+/// whose digit in that window is `d`.
+///
+/// Unit scalars are counted like any other, even though the MSM itself adds
+/// them outside of the buckets. Fails on the window sizes
+/// [`multi_scalar_mul_with_window_size`] rejects, and on windows past the top
+/// one.
+pub fn window_bucket_histogram<F: PrimeField>(
+    scalars: &[F::BigInt],
+    c: usize,
+    window_index: usize,
+) -> Result<Vec<usize>, MsmError> {
+    check_window_size::<F>(c)?;
+    let num_bits = F::Params::MODULUS_BITS as usize;
+    let w_start = window_index.checked_mul(c).ok_or(MsmError::ArithmeticOverflow)?;
+    if w_start >= num_bits {
+        let num_windows = (num_bits + c - 1) / c;
+        return Err(MsmError::WindowOutOfRange { window_index, num_windows });
+    }
+
+    let mut histogram = vec![0; num_buckets(c)];
+    for scalar in scalars {
+        let digit = window_digit(scalar, w_start, c);
+        if digit != 0 {
+            histogram[(digit - 1) as usize] += 1;
+        }
+    }
+    Ok(histogram)
+}
+
+/// Number of buckets of a `c`-bit window, `2^c - 1`, which overflows a 32-bit
+/// `usize` from `c = 32` on.
+fn checked_num_buckets(c: usize) -> Result<usize, MsmError> {
+    u32::try_from(c)
+        .ok()
+        .and_then(|c| 1usize.checked_shl(c))
+        .map(|buckets| buckets - 1)
+        .ok_or(MsmError::ArithmeticOverflow)
+}
+
+/// Same as [`checked_num_buckets`], for window sizes known to fit: picked by
+/// [`window_size`], or validated up front. Every bucket allocation goes
+/// through one of the two.
+fn num_buckets(c: usize) -> usize {
+    checked_num_buckets(c).expect("window sizes are checked before allocating buckets")
+}
+
+/// Computes the per-window sums of a `c`-bit window MSM, lowest window first,
+/// without the final fold, so that shards of an MSM run with the same `c` can be
+/// merged with [`merge_window_sums`] before a single [`combine_window_sums`].
+/// Fails on the window sizes [`multi_scalar_mul_with_window_size`] rejects.
+pub fn compute_window_sums<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    c: usize,
+) -> Result<Vec<G::Projective>, MsmError> {
+    check_window_size::<G::ScalarField>(c)?;
+
+    let num_bits = <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+    let size = core::cmp::min(bases.len(), scalars.len());
+    let scalars_and_bases_iter = scalars[..size].iter().zip(&bases[..size]).filter(|(s, _)| !s.is_zero());
+    let window_starts: Vec<_> = (0..num_bits).step_by(c).collect();
+    let mode = ReductionMode::default();
+    Ok(window_sums_at::<G, _, _, _>(scalars_and_bases_iter, &window_starts, c, mode, &mut OpCount::default()))
+}
+
+/// Adds up the window sums of every shard, window by window. All the shards
+/// must have been computed with the same window size.
+pub fn merge_window_sums<G: AffineCurve>(shards: &[Vec<G::Projective>]) -> Vec<G::Projective> {
+    let num_windows = shards.first().map_or(0, |shard| shard.len());
+    assert!(
+        shards.iter().all(|shard| shard.len() == num_windows),
+        "shards must have the same number of windows"
+    );
+
+    let mut merged = vec![G::Projective::zero(); num_windows];
+    for shard in shards {
+        merged.iter_mut().zip(shard).for_each(|(merged, sum)| *merged += sum);
+    }
+    merged
+}
+
+/// Folds the per-window sums, lowest window first, into the final result.
+///
+/// There is at least one window whenever `MODULUS_BITS > 0`, which holds for
+/// every prime field, but an empty slice is still mapped to the identity
+/// rather than panicking.
+pub fn combine_window_sums<G: PippengerGroup>(window_sums: &[G::Accumulator], c: usize) -> G::Accumulator {
+    fold_window_sums::<G>(window_sums, c, &mut OpCount::default())
+}
+
+/// Same as [`combine_window_sums`], counting the group operations into `ops`.
+fn fold_window_sums<G: PippengerGroup>(window_sums: &[G::Accumulator], c: usize, ops: &mut OpCount) -> G::Accumulator {
+    // We store the sum for the lowest window.
+    let (lowest, higher) = match window_sums.split_first() {
+        Some(split) => split,
+        None => return G::identity(),
+    };
+
+    // We're traversing windows from high to low.
+    let mut total = G::identity();
+    for sum_i in higher.iter().rev() {
+        G::add_accumulator(&mut total, sum_i);
+        for _ in 0..c {
+            G::double(&mut total);
+        }
+        ops.add(1);
+        ops.double(c);
+    }
+    G::add_accumulator(&mut total, lowest);
+    ops.add(1);
+    total
+}
+
+/// Computes `sum_{i in 0..num_buckets} (sum_{j in i..num_buckets} bucket[j])`,
+/// i.e. the sum of `(i + 1) * bucket[i]`, using 2b curve additions.
+fn reduce_buckets<G: PippengerGroup>(
+    buckets: &[G::Accumulator],
+    mode: ReductionMode,
+    ops: &mut OpCount,
+) -> G::Accumulator {
+    let mut res = G::identity();
+
+    // `running_sum` = sum_{j in i..num_buckets} bucket[j],
+    // where we iterate backward from i = num_buckets to 0.
+    let mut running_sum = G::identity();
+    match mode {
+        ReductionMode::Projective => {
+            buckets.iter().rev().for_each(|b| {
+                G::add_accumulator(&mut running_sum, b);
+                G::add_accumulator(&mut res, &running_sum);
+            });
+            ops.add(2 * buckets.len());
+        }
+        ReductionMode::BatchNormalizedMixed => {
+            // In the case of Short Weierstrass curves, mixed addition saves
+            // ~4 field multiplications per addition, while normalization
+            // (with the inversion batched) takes ~6 field multiplications
+            // per element, so this is expected to be a slowdown there; the
+            // `bench_reduction_mode` benchmark measures it per curve.
+            //
+            // Empty buckets are left out of the normalization, and only
+            // carry the running sum over.
+            let nonempty: Vec<_> = buckets.iter().filter(|b| !G::is_identity(b)).copied().collect();
+            let mut normalized = G::batch_normalize(&nonempty).into_iter().rev();
+            buckets.iter().rev().for_each(|b| {
+                if !G::is_identity(b) {
+                    let base = normalized.next().expect("one normalized point per nonempty bucket");
+                    G::add_mixed(&mut running_sum, &base);
+                }
+                G::add_accumulator(&mut res, &running_sum);
+            });
+            ops.add(nonempty.len() + buckets.len());
+        }
+    }
+    res
+}
+
+/// Replaces the reduced scalar `s` with `p - s`, i.e. its negation in `F`.
+pub fn negate_scalar_in_field<F: PrimeField>(s: &mut F::BigInt) {
+    if !s.is_zero() {
+        let mut neg = F::Params::MODULUS;
+        neg.sub_noborrow(s);
+        *s = neg;
+    }
+}
+
+/// Checks every scalar is reduced, i.e. below the modulus of `F`, returning the
+/// index of the first one that isn't. MSMs over unreduced scalars silently
+/// compute the wrong result, so this is worth running in debug builds.
+pub fn check_scalars_reduced<F: PrimeField>(scalars: &[F::BigInt]) -> Result<(), usize> {
+    match scalars.iter().position(|s| *s >= F::Params::MODULUS) {
+        Some(index) => Err(index),
+        None => Ok(()),
+    }
+}
+
+/// Reinterprets the reduced scalar `s` of `F1` as a scalar of `F2`, or `None`
+/// if its value is not below the modulus of `F2`.
+pub fn convert_scalar<F1: PrimeField, F2: PrimeField>(s: &F1::BigInt) -> Option<F2::BigInt> {
+    let mut converted = F2::BigInt::default();
+    let (src, dst) = (s.as_ref(), converted.as_mut());
+    let len = core::cmp::min(src.len(), dst.len());
+    if src[len..].iter().any(|&limb| limb != 0) {
+        return None;
+    }
+    dst[..len].copy_from_slice(&src[..len]);
+
+    if converted < F2::Params::MODULUS {
+        Some(converted)
+    } else {
+        None
+    }
+}
+
+/// Negates every base in place.
+pub fn batch_negate<G: AffineCurve>(bases: &mut [G]) {
+    bases.iter_mut().for_each(|base| *base = -*base);
+}
+
+/// Negates `bases[i]` in place wherever `flags[i]` is set.
+pub fn conditionally_negate_bases<G: AffineCurve>(bases: &mut [G], flags: &[bool]) {
+    assert_eq!(bases.len(), flags.len(), "one flag is needed per base");
+    bases
+        .iter_mut()
+        .zip(flags)
+        .filter(|(_, &flag)| flag)
+        .for_each(|(base, _)| *base = -*base);
+}
+
+/// Approximates `ln(a)` as `log2(a) * ln(2)`, with `ln(2) ~ 69 / 100`.
+///
+/// `log2` is `ark_std::log2`, which rounds up and maps `0` to `0`, so `a = 0`
+/// (only reachable for an empty MSM with a zero threshold) doesn't underflow.
+fn ln_without_floats(a: usize) -> usize {
+    ln_ratio(a, 69, 100)
+}
+
+/// Computes `log2(a) * num / den`, i.e. the logarithm of `a` in the base
+/// whose `log2` is `den / num`. The product is taken in `u64`, so it can't
+/// overflow for any `u32` ratio.
+fn ln_ratio(a: usize, num: u32, den: u32) -> usize {
+    (ark_std::log2(a) as u64 * num as u64 / den as u64) as usize
+}
+
+#[test]
+fn test() {
+    let size = 1<<14;
+    let (point_vec, scalar_vec) = generate_msm_inputs(size);
+    let res = compute_msm(point_vec, scalar_vec);
+}
+
+#[test]
+fn test_reduction_modes_agree() {
+    for size in [8, 100, 1 << 10] {
+        let (point_vec, scalar_vec) = generate_msm_inputs(size);
+        let projective = multi_scalar_mul_with_reduction(&point_vec, &scalar_vec, ReductionMode::Projective);
+        let mixed = multi_scalar_mul_with_reduction(&point_vec, &scalar_vec, ReductionMode::BatchNormalizedMixed);
+        assert_eq!(projective, mixed);
+    }
+}
+
+#[test]
+fn test_reduction_modes_agree_with_empty_buckets() {
+    let (point_vec, _) = generate_msm_inputs(16);
+    let mut buckets: Vec<_> = point_vec.iter().map(|p| p.into_projective()).collect();
+    for i in [0, 3, 4, 5, 15] {
+        buckets[i] = <G1Affine as AffineCurve>::Projective::zero();
+    }
+    assert_eq!(
+        reduce_buckets::<G1Affine>(&buckets, ReductionMode::BatchNormalizedMixed, &mut OpCount::default()),
+        reduce_buckets::<G1Affine>(&buckets, ReductionMode::Projective, &mut OpCount::default())
+    );
+}
+
+#[test]
+fn test_combine_window_sums_without_windows() {
+    use ark_bls12_381::G1Projective;
+
+    let res = combine_window_sums::<G1Affine>(&[], 3);
+    assert_eq!(res, G1Projective::zero());
+}
+
+#[test]
+fn test_negate_scalar_in_field() {
+    use ark_bls12_381::Fr;
+
+    let mut rng = ark_std::test_rng();
+    for _ in 0..10 {
+        let s = Fr::rand(&mut rng);
+        let mut repr = s.into_repr();
+        negate_scalar_in_field::<Fr>(&mut repr);
+        assert_eq!(Fr::from_repr(repr), Some(-s));
+        negate_scalar_in_field::<Fr>(&mut repr);
+        assert_eq!(repr, s.into_repr());
+    }
+
+    let mut zero = Fr::zero().into_repr();
+    negate_scalar_in_field::<Fr>(&mut zero);
+    assert!(zero.is_zero());
+}
+
+#[test]
+fn test_conditionally_negate_bases() {
+    let (point_vec, _) = generate_msm_inputs(16);
+
+    let mut negated = point_vec.clone();
+    batch_negate(&mut negated);
+    let mut all = point_vec.clone();
+    conditionally_negate_bases(&mut all, &[true; 16]);
+    assert_eq!(all, negated);
+
+    let flags: Vec<bool> = (0..16).map(|i| i % 3 == 0).collect();
+    let mut some = point_vec.clone();
+    conditionally_negate_bases(&mut some, &flags);
+    for i in 0..16 {
+        let expected = if flags[i] { negated[i] } else { point_vec[i] };
+        assert_eq!(some[i], expected);
+    }
+}
+
+#[test]
+fn test_multi_scalar_mul_matrix_col() {
+    let (point_vec, _) = generate_msm_inputs(64);
+    let cols = 3;
+    let mut rng = ark_std::test_rng();
+    let matrix: Vec<_> = (0..point_vec.len() * cols)
+        .map(|_| <G1Affine as AffineCurve>::ScalarField::rand(&mut rng).into_repr())
+        .collect();
+
+    for col in 0..cols {
+        let column: Vec<_> = matrix.iter().skip(col).step_by(cols).cloned().collect();
+        assert_eq!(
+            multi_scalar_mul_matrix_col(&point_vec, &matrix, cols, col),
+            multi_scalar_mul(&point_vec, &column),
+        );
+    }
+}
+
+#[test]
+fn test_multi_scalar_mul_neg() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    let neg = multi_scalar_mul_neg(&point_vec, &scalar_vec);
+    assert_eq!(neg, -multi_scalar_mul(&point_vec, &scalar_vec));
+    assert!((neg + multi_scalar_mul(&point_vec, &scalar_vec)).is_zero());
+}
+
+#[test]
+fn test_multi_scalar_mul_with_threshold() {
+    for size in [SMALL_INPUT_THRESHOLD - 1, SMALL_INPUT_THRESHOLD, SMALL_INPUT_THRESHOLD + 1, 64, 65] {
+        let (point_vec, scalar_vec) = generate_msm_inputs(size);
+        let expected = multi_scalar_mul(&point_vec, &scalar_vec);
+        for threshold in [0, 16, 64, usize::MAX] {
+            assert_eq!(multi_scalar_mul_with_threshold(&point_vec, &scalar_vec, threshold), expected);
+        }
+    }
+}
+
+#[test]
+fn test_multi_scalar_mul_u64_limbs() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    let limbs: Vec<[u64; 4]> = scalar_vec.iter().map(|s| s.0).collect();
+    assert_eq!(
+        multi_scalar_mul_u64_limbs(&point_vec, &limbs),
+        multi_scalar_mul(&point_vec, &scalar_vec),
+    );
+}
+
+#[test]
+fn test_multi_scalar_mul_cancellable() {
+    use std::cell::Cell;
+
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    assert_eq!(
+        multi_scalar_mul_cancellable(&point_vec, &scalar_vec, &|| false),
+        Some(multi_scalar_mul(&point_vec, &scalar_vec)),
+    );
+
+    // Cancel once the first window is done.
+    let polls = Cell::new(0);
+    let should_cancel = || {
+        polls.set(polls.get() + 1);
+        polls.get() > 1
+    };
+    assert_eq!(multi_scalar_mul_cancellable(&point_vec, &scalar_vec, &should_cancel), None);
+    assert_eq!(polls.get(), 2);
+}
+
+#[test]
+fn test_ln_without_floats() {
+    assert_eq!(ln_without_floats(0), 0);
+    assert_eq!(ln_without_floats(1), 0);
+    assert_eq!(ln_without_floats(2), 0);
+    assert_eq!(ln_without_floats(32), 3);
+    assert_eq!(ln_without_floats(1024), 6);
+    assert_eq!(ln_without_floats(1 << 20), 13);
+}
+
+#[test]
+fn test_window_bucket_histogram() {
+    use ark_bls12_381::Fr;
+
+    let (_, scalar_vec) = generate_msm_inputs(1 << 10);
+    let c = 4;
+    for window_index in [0, 1, 31, 63] {
+        let histogram = window_bucket_histogram::<Fr>(&scalar_vec, c, window_index).unwrap();
+        assert_eq!(histogram.len(), (1 << c) - 1);
+
+        let nonzero_digits = scalar_vec
+            .iter()
+            .filter(|s| (0..c).any(|bit| s.get_bit(window_index * c + bit)))
+            .count();
+        assert_eq!(histogram.iter().sum::<usize>(), nonzero_digits);
+    }
+
+    assert_eq!(
+        window_bucket_histogram::<Fr>(&scalar_vec, c, 64),
+        Err(MsmError::WindowOutOfRange { window_index: 64, num_windows: 64 }),
+    );
+    assert_eq!(
+        window_bucket_histogram::<Fr>(&scalar_vec, 0, 0),
+        Err(MsmError::InvalidWindowSize { c: 0, max: MAX_BUCKET_WINDOW_BITS }),
+    );
+}
+
+#[test]
+fn test_multi_scalar_mul_transposed() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    let (rows, cols) = (9, 11);
+    let transposed = &point_vec[..rows * cols];
+    let natural: Vec<_> = (0..rows * cols)
+        .map(|i| transposed[(i % cols) * rows + i / cols])
+        .collect();
+    assert_eq!(
+        multi_scalar_mul_transposed(transposed, &scalar_vec, cols),
+        multi_scalar_mul(&natural, &scalar_vec)
+    );
+    assert_eq!(
+        multi_scalar_mul_transposed(transposed, &scalar_vec, 1),
+        multi_scalar_mul(transposed, &scalar_vec)
+    );
+}
+
+#[test]
+#[should_panic(expected = "whole rows")]
+fn test_multi_scalar_mul_transposed_rejects_partial_rows() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    multi_scalar_mul_transposed(&point_vec[..99], &scalar_vec, 10);
+}
+
+#[test]
+fn test_multi_scalar_mul_soa() {
+    let (mut point_vec, scalar_vec) = generate_msm_inputs(100);
+    point_vec[7] = G1Affine::zero();
+
+    let xs: Vec<_> = point_vec.iter().map(|p| p.x).collect();
+    let ys: Vec<_> = point_vec.iter().map(|p| p.y).collect();
+    let infinity: Vec<_> = point_vec.iter().map(|p| p.infinity).collect();
+    assert_eq!(
+        multi_scalar_mul_soa(&xs, &ys, &infinity, &scalar_vec),
+        multi_scalar_mul(&point_vec, &scalar_vec),
+    );
+}
+
+#[test]
+fn test_multi_scalar_mul_matches_arkworks() {
+    use ark_bls12_381::{Fr, G2Affine, G2Projective};
+    use ark_ec::msm::VariableBaseMSM;
+
+    for size in [1, 2, 31, 32, 33, 100, 1 << 10] {
+        let (point_vec, mut scalar_vec) = generate_msm_inputs(size + 1);
+        // Exercise the zero and unit scalar paths too.
+        scalar_vec[0] = Fr::zero().into_repr();
+        scalar_vec[size / 2] = Fr::one().into_repr();
+        assert_eq!(
+            multi_scalar_mul(&point_vec, &scalar_vec),
+            VariableBaseMSM::multi_scalar_mul(&point_vec, &scalar_vec),
+        );
+    }
+
+    let mut rng = ark_std::test_rng();
+    let g2_points: Vec<G2Projective> = (0..64).map(|_| G2Projective::rand(&mut rng)).collect();
+    let g2_points: Vec<G2Affine> = G2Projective::batch_normalization_into_affine(&g2_points);
+    let scalar_vec: Vec<_> = (0..64).map(|_| Fr::rand(&mut rng).into_repr()).collect();
+    assert_eq!(
+        multi_scalar_mul(&g2_points, &scalar_vec),
+        VariableBaseMSM::multi_scalar_mul(&g2_points, &scalar_vec),
+    );
+}
+
+#[test]
+fn test_multi_scalar_mul_nofilter() {
+    let (point_vec, mut scalar_vec) = generate_msm_inputs(1 << 10);
+    assert_eq!(multi_scalar_mul_nofilter(&point_vec, &scalar_vec), multi_scalar_mul(&point_vec, &scalar_vec));
+
+    let zero = <G1Affine as AffineCurve>::ScalarField::zero().into_repr();
+    scalar_vec.iter_mut().enumerate().filter(|(i, _)| i % 8 != 0).for_each(|(_, s)| *s = zero);
+    assert_eq!(multi_scalar_mul_nofilter(&point_vec, &scalar_vec), multi_scalar_mul(&point_vec, &scalar_vec));
+
+    scalar_vec.iter_mut().for_each(|s| *s = zero);
+    assert!(multi_scalar_mul_nofilter(&point_vec, &scalar_vec).is_zero());
+}
+
+#[test]
+fn test_window_arithmetic_does_not_wrap() {
+    // The largest representable input still gets a window whose buckets fit.
+    for size in [u32::MAX as usize, usize::MAX] {
+        assert!(checked_num_buckets(window_size(size, SMALL_INPUT_THRESHOLD)).is_ok());
+    }
+    assert_eq!(checked_num_buckets(usize::BITS as usize), Err(MsmError::ArithmeticOverflow));
+
+    let (_, scalar_vec) = generate_msm_inputs(4);
+    assert_eq!(
+        window_bucket_histogram::<ark_bls12_381::Fr>(&scalar_vec, 2, usize::MAX),
+        Err(MsmError::ArithmeticOverflow),
+    );
+}
+
+#[test]
+fn test_msm_engine() {
+    fn assert_send<T: Send>() {}
+    assert_send::<MsmEngine<G1Affine>>();
+
+    let mut engine = MsmEngine::new();
+    for size in [100, 1 << 10, 8, 1 << 10] {
+        let (point_vec, scalar_vec) = generate_msm_inputs(size);
+        assert_eq!(engine.msm(&point_vec, &scalar_vec), multi_scalar_mul(&point_vec, &scalar_vec));
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_msm_engine_with_thread_pool() {
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+    let mut engine = MsmEngine::with_thread_pool(pool);
+    for size in [100, 1 << 10] {
+        let (point_vec, scalar_vec) = generate_msm_inputs(size);
+        assert_eq!(engine.msm(&point_vec, &scalar_vec), multi_scalar_mul(&point_vec, &scalar_vec));
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_multi_scalar_mul_tiled() {
+    let (point_vec, mut scalar_vec) = generate_msm_inputs(1 << 10);
+    let expected = multi_scalar_mul(&point_vec, &scalar_vec);
+    for tile_len in [1, 100, 1 << 10, 1 << 12, default_tile_len(point_vec.len())] {
+        assert_eq!(multi_scalar_mul_tiled(&point_vec, &scalar_vec, tile_len), expected);
+    }
+
+    // Skewed scalars, with all the digits in the lowest windows.
+    scalar_vec.iter_mut().enumerate().for_each(|(i, s)| *s = (i as u64).into());
+    assert_eq!(multi_scalar_mul_tiled(&point_vec, &scalar_vec, 128), multi_scalar_mul(&point_vec, &scalar_vec));
+    assert!(multi_scalar_mul_tiled::<G1Affine>(&[], &[], 128).is_zero());
+}
+
+#[test]
+fn test_multi_scalar_mul_sparse() {
+    let (point_vec, mut scalar_vec) = generate_msm_inputs(1 << 8);
+    let zero = <G1Affine as AffineCurve>::ScalarField::zero().into_repr();
+    scalar_vec.iter_mut().enumerate().filter(|(i, _)| i % 5 != 0).for_each(|(_, s)| *s = zero);
+
+    let entries: Vec<_> = scalar_vec
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| !s.is_zero())
+        .map(|(i, s)| (i, *s))
+        .collect();
+    assert_eq!(multi_scalar_mul_sparse(&point_vec, &entries), multi_scalar_mul(&point_vec, &scalar_vec));
+}
+
+#[test]
+#[should_panic(expected = "out of range")]
+fn test_multi_scalar_mul_sparse_rejects_bad_index() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(4);
+    multi_scalar_mul_sparse(&point_vec, &[(4, scalar_vec[0])]);
+}
+
+#[test]
+fn test_single_window_does_not_clone() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Counts how many times the wrapped iterator is cloned.
+    struct CountingIter<I> {
+        inner: I,
+        clones: Rc<Cell<usize>>,
+    }
+
+    impl<I: Clone> Clone for CountingIter<I> {
+        fn clone(&self) -> Self {
+            self.clones.set(self.clones.get() + 1);
+            Self { inner: self.inner.clone(), clones: self.clones.clone() }
+        }
+    }
+
+    impl<I: Iterator> Iterator for CountingIter<I> {
+        type Item = I::Item;
+
+        fn next(&mut self) -> Option<I::Item> {
+            self.inner.next()
+        }
+    }
+
+    // 16-bit scalars fit in a single 16-bit window.
+    let (point_vec, _) = generate_msm_inputs(64);
+    let scalar_vec: Vec<_> = (0..64u64)
+        .map(|i| <<G1Affine as AffineCurve>::ScalarField as PrimeField>::BigInt::from(i * 997 % (1 << 16)))
+        .collect();
+    let expected = multi_scalar_mul(&point_vec, &scalar_vec);
+    let mode = ReductionMode::default();
+
+    let clones = Rc::new(Cell::new(0));
+    let iter = CountingIter { inner: scalar_vec.iter().zip(&point_vec), clones: clones.clone() };
+    let window_sums = window_sums_at::<G1Affine, _, _, _>(iter, &[0], 16, mode, &mut OpCount::default());
+    assert_eq!(clones.get(), 0);
+    assert_eq!(combine_window_sums::<G1Affine>(&window_sums, 16), expected);
+
+    let iter = CountingIter { inner: scalar_vec.iter().zip(&point_vec), clones: clones.clone() };
+    let window_sums = window_sums_at::<G1Affine, _, _, _>(iter, &[0, 8], 8, mode, &mut OpCount::default());
+    assert_eq!(clones.get(), 2);
+    assert_eq!(combine_window_sums::<G1Affine>(&window_sums, 8), expected);
+}
+
+#[test]
+fn test_convert_scalar() {
+    use ark_bls12_381::Fr;
+    use ark_bn254::Fr as BnFr;
+
+    let small = Fr::from(123456789u64).into_repr();
+    let converted = convert_scalar::<Fr, BnFr>(&small).unwrap();
+    assert_eq!(BnFr::from_repr(converted), Some(BnFr::from(123456789u64)));
+
+    // The BLS12-381 scalar modulus is larger than the BN254 one.
+    let large = (-Fr::one()).into_repr();
+    assert_eq!(convert_scalar::<Fr, BnFr>(&large), None);
+    // While every BN254 scalar fits in the BLS12-381 scalar field.
+    assert!(convert_scalar::<BnFr, Fr>(&(-BnFr::one()).into_repr()).is_some());
+}
+
+#[test]
+fn test_ln_ratio() {
+    for a in [0, 1, 2, 3, 31, 32, 1000, 1024, 1 << 20, usize::MAX] {
+        assert_eq!(ln_ratio(a, 69, 100), (ark_std::log2(a) * 69 / 100) as usize);
+    }
+    // log2 itself, and no overflow with a large ratio.
+    assert_eq!(ln_ratio(1024, 1, 1), 10);
+    assert_eq!(ln_ratio(1024, u32::MAX, u32::MAX), 10);
+}
+
+#[test]
+fn test_multi_scalar_mul_signed_input() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(65);
+    let size = scalar_vec.len();
+    let mut signs = vec![false; size];
+    assert_eq!(
+        multi_scalar_mul_signed_input(&point_vec, &scalar_vec, &signs),
+        multi_scalar_mul(&point_vec, &scalar_vec),
+    );
+
+    // Flipping a sign flips that term's contribution.
+    let unsigned = multi_scalar_mul(&point_vec, &scalar_vec);
+    signs[3] = true;
+    let term = point_vec[3].mul(scalar_vec[3]);
+    assert_eq!(multi_scalar_mul_signed_input(&point_vec, &scalar_vec, &signs), unsigned - term.double());
+
+    let signs: Vec<bool> = (0..size).map(|i| i % 2 == 1).collect();
+    let negated: Vec<_> = point_vec.iter().zip(&signs).map(|(b, &neg)| if neg { -*b } else { *b }).collect();
+    assert_eq!(
+        multi_scalar_mul_signed_input(&point_vec, &scalar_vec, &signs),
+        multi_scalar_mul(&negated, &scalar_vec),
+    );
+}
+
+#[test]
+fn test_check_scalars_reduced() {
+    use ark_bls12_381::Fr;
+    use ark_ff::FpParameters;
+
+    let (_, mut scalar_vec) = generate_msm_inputs(100);
+    assert_eq!(check_scalars_reduced::<Fr>(&scalar_vec), Ok(()));
+
+    scalar_vec[17] = <Fr as PrimeField>::Params::MODULUS;
+    scalar_vec[42] = <Fr as PrimeField>::Params::MODULUS;
+    assert_eq!(check_scalars_reduced::<Fr>(&scalar_vec), Err(17));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_calibrate_window_size() {
+    let num_bits = <<G1Affine as AffineCurve>::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+    let mut rng = ark_std::test_rng();
+    let c = calibrate_window_size::<G1Affine, _>(64, &mut rng);
+    assert!(c >= 1 && c <= num_bits);
+    assert_eq!(calibrate_window_size::<G1Affine, _>(60, &mut rng), c);
+
+    let (point_vec, scalar_vec) = generate_msm_inputs(64);
+    assert_eq!(
+        multi_scalar_mul_with_window_size(&point_vec, &scalar_vec, c).unwrap(),
+        multi_scalar_mul(&point_vec, &scalar_vec),
+    );
+}
+
+#[test]
+fn test_compare_backends() {
+    use std::time::Duration;
+
+    let (point_vec, scalar_vec) = generate_msm_inputs(64);
+    let timings = compare_backends(&point_vec, &scalar_vec, 3);
+    assert!(timings.naive > Duration::ZERO);
+    assert!(timings.pippenger > Duration::ZERO);
+    #[cfg(feature = "parallel")]
+    assert!(timings.parallel > Duration::ZERO);
+}
+
+#[test]
+fn test_multi_scalar_mul_coset() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    for (stride, offset) in [(1, 0), (4, 0), (4, 3), (7, 5), (200, 42)] {
+        let coset_bases: Vec<_> = point_vec[..99].iter().copied().skip(offset).step_by(stride).collect();
+        let coset_scalars: Vec<_> = scalar_vec.iter().copied().skip(offset).step_by(stride).collect();
+        assert_eq!(
+            multi_scalar_mul_coset(&point_vec, &scalar_vec, stride, offset),
+            multi_scalar_mul(&coset_bases, &coset_scalars)
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn test_multi_scalar_mul_coset_rejects_bad_offset() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    multi_scalar_mul_coset(&point_vec, &scalar_vec, 4, 99);
+}
+
+#[test]
+fn test_multi_scalar_mul_periodic() {
+    let period = 7;
+    let (point_vec, _) = generate_msm_inputs(period);
+    let mut rng = ark_std::test_rng();
+    let scalar_vec: Vec<_> = (0..period * 9)
+        .map(|_| <G1Affine as AffineCurve>::ScalarField::rand(&mut rng).into_repr())
+        .collect();
+
+    let expanded: Vec<_> = point_vec.iter().cycle().take(scalar_vec.len()).cloned().collect();
+    let expected = multi_scalar_mul(&expanded, &scalar_vec);
+    assert_eq!(multi_scalar_mul_periodic(&point_vec, &scalar_vec, period), expected);
+
+    let mut unreduced = scalar_vec.clone();
+    unreduced[3].add_nocarry(&<<G1Affine as AffineCurve>::ScalarField as PrimeField>::Params::MODULUS);
+    assert_eq!(multi_scalar_mul_periodic(&point_vec, &unreduced, period), expected);
+}
+
+#[test]
+fn test_multi_scalar_mul_columns() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    let mut rng = ark_std::test_rng();
+    let mut other: Vec<_> = (0..scalar_vec.len())
+        .map(|_| <G1Affine as AffineCurve>::ScalarField::rand(&mut rng).into_repr())
+        .collect();
+    other[0] = <G1Affine as AffineCurve>::ScalarField::one().into_repr();
+    other[1] = <G1Affine as AffineCurve>::ScalarField::zero().into_repr();
+
+    let res = multi_scalar_mul_columns(&point_vec, &[&scalar_vec, &other]);
+    assert_eq!(res, vec![multi_scalar_mul(&point_vec, &scalar_vec), multi_scalar_mul(&point_vec, &other)]);
+    assert!(multi_scalar_mul_columns::<G1Affine>(&point_vec, &[]).is_empty());
+}
+
+#[test]
+fn test_multi_scalar_mul_zeropad() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    assert!(point_vec.len() > scalar_vec.len());
+
+    let mut padded = scalar_vec.clone();
+    padded.resize(point_vec.len(), <G1Affine as AffineCurve>::ScalarField::zero().into_repr());
+    let res = multi_scalar_mul_zeropad(&point_vec, &scalar_vec);
+    assert_eq!(res, multi_scalar_mul(&point_vec, &padded));
+    assert_eq!(res, multi_scalar_mul(&point_vec[..scalar_vec.len()], &scalar_vec));
+}
+
+#[test]
+fn test_accumulator_is_jacobian() {
+    use ark_bls12_381::g1::Parameters;
+    use ark_ff::Field;
+    use std::any::TypeId;
+
+    assert_eq!(
+        TypeId::of::<<G1Affine as AffineCurve>::Projective>(),
+        TypeId::of::<GroupProjective<Parameters>>()
+    );
+
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    let res = multi_scalar_mul(&point_vec, &scalar_vec);
+    let affine = res.into_affine();
+    let z2 = res.z.square();
+    assert_eq!(res.x, affine.x * z2);
+    assert_eq!(res.y, affine.y * z2 * res.z);
+
+    // Rescaling the three coordinates keeps the same point.
+    let lambda = <Parameters as ark_ec::ModelParameters>::BaseField::from(7u64);
+    let lambda2 = lambda.square();
+    let rescaled = GroupProjective::<Parameters>::new(res.x * lambda2, res.y * lambda2 * lambda, res.z * lambda);
+    assert_eq!(rescaled, res);
+    assert_eq!(rescaled.into_affine(), affine);
+}
+
+#[test]
+fn test_window_reducer() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(50);
+    let c = 4;
+    let mut reducer = WindowReducer::<G1Affine>::new(c).unwrap();
+    assert!(reducer.current_sum().is_zero());
+
+    let from_scratch = |terms: usize| {
+        let scalars_and_bases = scalar_vec[..terms].iter().zip(&point_vec);
+        window_sum::<G1Affine, _, _, _>(scalars_and_bases, 8, c, ReductionMode::default())
+    };
+    for k in 0..scalar_vec.len() {
+        reducer.add(window_digit(&scalar_vec[k], 8, c), &point_vec[k]);
+        if k % 7 == 0 {
+            assert_eq!(reducer.current_sum(), from_scratch(k + 1));
+        }
+    }
+    // The cached sum is reused until more terms are added.
+    assert_eq!(reducer.current_sum(), from_scratch(scalar_vec.len()));
+    assert_eq!(reducer.current_sum(), from_scratch(scalar_vec.len()));
+}
+
+#[test]
+fn test_msm_precomp_extend() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    let all = MsmPrecomp::new(&point_vec);
+    assert_eq!(all.eval(&scalar_vec), multi_scalar_mul(&point_vec, &scalar_vec));
+
+    let mut extended = MsmPrecomp::new(&point_vec[..50]);
+    extended.extend(&point_vec[50..]);
+    assert_eq!(extended.len(), point_vec.len());
+    assert_eq!(extended.eval(&scalar_vec), all.eval(&scalar_vec));
+}
+
+#[test]
+fn test_msm_single_update() {
+    let (point_vec, mut scalar_vec) = generate_msm_inputs(100);
+    let mut msm = MsmSingleUpdate::new(&point_vec, &scalar_vec);
+    assert_eq!(msm.result(), multi_scalar_mul(&point_vec, &scalar_vec));
+
+    let one = <G1Affine as AffineCurve>::ScalarField::one().into_repr();
+    let zero = <G1Affine as AffineCurve>::ScalarField::zero().into_repr();
+    for (index, scalar) in [(7, scalar_vec[3]), (42, one), (0, zero), (42, scalar_vec[98])] {
+        scalar_vec[index] = scalar;
+        assert_eq!(msm.update(index, scalar), multi_scalar_mul(&point_vec, &scalar_vec));
+    }
+}
+
+#[test]
+fn test_multi_scalar_mul_signed_digits() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    assert_eq!(multi_scalar_mul_signed_digits(&point_vec, &scalar_vec), multi_scalar_mul(&point_vec, &scalar_vec));
+
+    let num_bits = <<G1Affine as AffineCurve>::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+    let c = window_size(scalar_vec.len(), SMALL_INPUT_THRESHOLD);
+    for s in &scalar_vec {
+        let digits = signed_window_digits(s, c, num_bits);
+        assert!(digits.iter().all(|&d| d.abs() <= 1 << (c - 1)));
+    }
+}
+
+#[test]
+fn test_multi_scalar_mul_naf() {
+    use ark_ff::BigInteger256;
+
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    assert_eq!(multi_scalar_mul_naf(&point_vec, &scalar_vec), multi_scalar_mul(&point_vec, &scalar_vec));
+    assert_eq!(multi_scalar_mul_naf(&point_vec[..1], &scalar_vec[..1]), point_vec[0].mul(scalar_vec[0]));
+
+    for (value, c) in [(0u64, 2), (1, 2), (0b1011, 2), (0b1011, 3), (u64::MAX, 5), (0xdead_beef, 7)] {
+        let digits = wnaf_digits(&BigInteger256::from(value), c);
+        let recoded: i128 = digits.iter().enumerate().map(|(i, &d)| (d as i128) << i).sum();
+        assert_eq!(recoded, value as i128);
+    }
+
+    // Count the bucket additions (nonzero digits) of both recodings, with
+    // the width the MSM uses.
+    let num_bits = <<G1Affine as AffineCurve>::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+    let c = naf_width(scalar_vec.len());
+    let unsigned_additions: usize = scalar_vec
+        .iter()
+        .map(|s| (0..num_bits).step_by(c).filter(|&w_start| window_digit(s, w_start, c) != 0).count())
+        .sum();
+    let mut naf_additions = 0;
+    for s in &scalar_vec {
+        let digits = wnaf_digits(s, c);
+        for (i, &d) in digits.iter().enumerate().filter(|&(_, &d)| d != 0) {
+            assert!(d % 2 != 0 && d.abs() < 1 << (c - 1));
+            assert!(digits[i + 1..].iter().take(c - 1).all(|&d| d == 0));
+            naf_additions += 1;
+        }
+    }
+    assert!(naf_additions < unsigned_additions, "{} NAF vs {} unsigned additions", naf_additions, unsigned_additions);
+}
+
+#[test]
+fn test_multi_scalar_mul_partial() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    let partials = [0..30, 30..30, 30..64, 64..99]
+        .into_iter()
+        .map(|range| multi_scalar_mul_partial(&point_vec, &scalar_vec, range))
+        .sum::<<G1Affine as AffineCurve>::Projective>();
+    assert_eq!(partials, multi_scalar_mul(&point_vec, &scalar_vec));
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn test_multi_scalar_mul_partial_rejects_bad_range() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    multi_scalar_mul_partial(&point_vec, &scalar_vec, 50..100);
+}
+
+#[test]
+fn test_multi_scalar_mul_until() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    let expected = multi_scalar_mul(&point_vec, &scalar_vec);
+    assert_eq!(multi_scalar_mul_until(&point_vec, &scalar_vec, &expected), (expected, true));
+
+    let target = expected.double();
+    assert_eq!(multi_scalar_mul_until(&point_vec, &scalar_vec, &target), (expected, false));
+
+    // The top window alone is the MSM of the scalars shifted down to it, which
+    // matches right after the first window.
+    let num_bits = <<G1Affine as AffineCurve>::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+    let c = window_size(scalar_vec.len(), SMALL_INPUT_THRESHOLD);
+    let top_start = (num_bits - 1) / c * c;
+    let shifted: Vec<_> = scalar_vec
+        .iter()
+        .map(|s| {
+            let mut s = *s;
+            s.divn(top_start as u32);
+            s
+        })
+        .collect();
+    let top = multi_scalar_mul(&point_vec, &shifted);
+    assert_ne!(top, expected);
+    assert_eq!(multi_scalar_mul_until(&point_vec, &scalar_vec, &top), (top, true));
+}
+
+#[test]
+fn test_random_subgroup_points() {
+    use ark_bls12_381::G2Affine;
+
+    let mut rng = ark_std::test_rng();
+    let p: G1Affine = random_subgroup_point(&mut rng);
+    assert!(p.is_on_curve() && p.is_in_correct_subgroup_assuming_on_curve());
+
+    let points: Vec<G2Affine> = random_subgroup_points(16, &mut rng);
+    assert_eq!(points.len(), 16);
+    assert!(points.iter().all(|p| p.is_on_curve() && p.is_in_correct_subgroup_assuming_on_curve()));
+}
+
+#[test]
+fn test_multi_scalar_mul_montgomery() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    let scalars: Vec<_> = scalar_vec
+        .iter()
+        .map(|s| <G1Affine as AffineCurve>::ScalarField::from_repr(*s).unwrap())
+        .collect();
+
+    // Stale contents of the cache must not leak into the result.
+    let mut repr_buf = scalar_vec[..10].to_vec();
+    let res = multi_scalar_mul_montgomery(&point_vec, &scalars, &mut repr_buf);
+    assert_eq!(res, multi_scalar_mul_fr(&point_vec, &scalars));
+    assert_eq!(repr_buf, scalar_vec);
+}
+
+#[test]
+fn test_merge_window_sums() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    let c = 5;
+    let shards: Vec<_> = [0..40, 40..41, 41..99]
+        .into_iter()
+        .map(|range| compute_window_sums(&point_vec[range.clone()], &scalar_vec[range], c).unwrap())
+        .collect();
+
+    let merged = merge_window_sums::<G1Affine>(&shards);
+    assert_eq!(
+        combine_window_sums::<G1Affine>(&merged, c),
+        multi_scalar_mul(&point_vec, &scalar_vec)
+    );
+}
+
+#[test]
+fn test_multi_scalar_mul_generator_powers() {
+    let mut rng = ark_std::test_rng();
+    let g = G1Affine::prime_subgroup_generator();
+    let exponents: Vec<_> = (0..50).map(|_| <G1Affine as AffineCurve>::ScalarField::rand(&mut rng)).collect();
+    let scalars: Vec<_> = (0..50).map(|_| <G1Affine as AffineCurve>::ScalarField::rand(&mut rng)).collect();
+
+    let bases: Vec<_> = exponents.iter().map(|e| g.mul(e.into_repr())).collect();
+    let bases = <G1Affine as AffineCurve>::Projective::batch_normalization_into_affine(&bases);
+    assert_eq!(
+        multi_scalar_mul_generator_powers(g, &exponents, &scalars),
+        multi_scalar_mul_fr(&bases, &scalars)
+    );
+}
+
+#[test]
+fn test_multi_scalar_mul_prefix() {
+    let (point_vec, mut scalar_vec) = generate_msm_inputs(40);
+    scalar_vec[3] = <G1Affine as AffineCurve>::ScalarField::zero().into_repr();
+
+    let prefixes = multi_scalar_mul_prefix(&point_vec, &scalar_vec);
+    assert_eq!(prefixes.len(), scalar_vec.len());
+    for (k, prefix) in prefixes.iter().enumerate() {
+        assert_eq!(*prefix, multi_scalar_mul(&point_vec[..=k], &scalar_vec[..=k]));
+    }
+}
+
+#[test]
+fn test_multi_scalar_mul_tuples() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    let pairs: Vec<_> = point_vec.iter().copied().zip(scalar_vec.iter().copied()).collect();
+    assert_eq!(multi_scalar_mul_tuples(&pairs), multi_scalar_mul(&point_vec, &scalar_vec));
+}
+
+#[test]
+fn test_multi_scalar_mul_scalar_stream() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    assert_eq!(
+        multi_scalar_mul_scalar_stream(&point_vec, scalar_vec.iter().copied()),
+        multi_scalar_mul(&point_vec, &scalar_vec)
+    );
+
+    // Scalars produced on the fly, more of them than there are bases.
+    let stream = (0..200u64).map(|i| <G1Affine as AffineCurve>::ScalarField::from(i * i + 1).into_repr());
+    let collected: Vec<_> = stream.clone().collect();
+    assert_eq!(
+        multi_scalar_mul_scalar_stream(&point_vec, stream),
+        multi_scalar_mul(&point_vec, &collected)
+    );
+}
+
+#[test]
+fn test_multi_scalar_mul_nonempty() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    assert_eq!(multi_scalar_mul_nonempty::<G1Affine>(&[], &[]), Err(MsmError::EmptyInput));
+    assert_eq!(multi_scalar_mul_nonempty(&point_vec, &[]), Err(MsmError::EmptyInput));
+    assert_eq!(
+        multi_scalar_mul_nonempty(&point_vec, &scalar_vec),
+        Ok(multi_scalar_mul(&point_vec, &scalar_vec))
+    );
+}
+
+#[cfg(feature = "validate-outputs")]
+#[test]
+fn test_multi_scalar_mul_validated() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    assert_eq!(multi_scalar_mul_validated(&point_vec, &scalar_vec), multi_scalar_mul(&point_vec, &scalar_vec));
+    assert!(multi_scalar_mul_validated::<ark_bls12_381::g1::Parameters>(&[], &[]).is_zero());
+}
+
+#[cfg(feature = "validate-outputs")]
+#[test]
+#[should_panic(expected = "off the curve")]
+fn test_multi_scalar_mul_validated_rejects_corrupted_result() {
+    use ark_ff::Field;
+
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    let mut corrupted = multi_scalar_mul(&point_vec, &scalar_vec);
+    corrupted.y.double_in_place();
+    assert_msm_output_on_curve(&corrupted);
+}
+
+#[test]
+fn test_multi_scalar_mul_x() {
+    let (point_vec, mut scalar_vec) = generate_msm_inputs(100);
+    let res = multi_scalar_mul(&point_vec, &scalar_vec);
+    assert!(!res.is_zero());
+    assert_eq!(multi_scalar_mul_x(&point_vec, &scalar_vec), Some(res.into_affine().x));
+
+    scalar_vec.iter_mut().for_each(|s| *s = <G1Affine as AffineCurve>::ScalarField::zero().into_repr());
+    assert_eq!(multi_scalar_mul_x(&point_vec, &scalar_vec), None);
+}
+
+#[test]
+fn test_multi_scalar_mul_bits() {
+    let (point_vec, _) = generate_msm_inputs(100);
+    let bits: Vec<bool> = (0..point_vec.len()).map(|i| i % 3 != 1).collect();
+    let scalars: Vec<_> = bits.iter().map(|&bit| (bit as u64).into()).collect();
+    assert_eq!(multi_scalar_mul_bits(&point_vec, &bits), multi_scalar_mul(&point_vec, &scalars));
+}
+
+#[test]
+fn test_multi_scalar_mul_dual() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    let other: Vec<_> = scalar_vec.iter().rev().copied().collect();
+    assert_eq!(
+        multi_scalar_mul_dual(&point_vec, &scalar_vec, &other),
+        (multi_scalar_mul(&point_vec, &scalar_vec), multi_scalar_mul(&point_vec, &other))
+    );
+}
+
+#[test]
+fn test_multi_scalar_mul_jacobian() {
+    use ark_bls12_381::g1::Parameters;
+    use ark_bls12_381::Fq;
+
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    let (x, y, z) = multi_scalar_mul_jacobian(&point_vec, &scalar_vec);
+    assert_eq!(GroupProjective::<Parameters>::new(x, y, z), multi_scalar_mul(&point_vec, &scalar_vec));
+
+    let zeros = vec![<G1Affine as AffineCurve>::ScalarField::zero().into_repr(); scalar_vec.len()];
+    assert_eq!(multi_scalar_mul_jacobian(&point_vec, &zeros), (Fq::one(), Fq::one(), Fq::zero()));
+}
+
+#[test]
+fn test_batch_into_repr() {
+    let mut rng = ark_std::test_rng();
+    let fs: Vec<_> = (0..100).map(|_| <G1Affine as AffineCurve>::ScalarField::rand(&mut rng)).collect();
+    let expected: Vec<_> = fs.iter().map(|f| f.into_repr()).collect();
+    assert_eq!(batch_into_repr(&fs), expected);
+}
+
+#[test]
+fn test_multi_scalar_mul_counted() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    let (res, ops) = multi_scalar_mul_counted(&point_vec, &scalar_vec);
+    assert_eq!(res, multi_scalar_mul(&point_vec, &scalar_vec));
+    assert_eq!(multi_scalar_mul_counted(&point_vec, &scalar_vec).1, ops);
+
+    // Random digits are only zero once in 2^c, and the top window is short.
+    let estimate = estimate_msm_ops::<G1Affine>(scalar_vec.len());
+    assert!(ops <= estimate, "{} operations, estimated {}", ops, estimate);
+    assert!(ops * 100 >= estimate * 95, "{} operations, estimated {}", ops, estimate);
+
+    // Identity bases are filtered out before costing anything.
+    let mut with_identity = point_vec.clone();
+    with_identity[0] = G1Affine::zero();
+    assert!(multi_scalar_mul_counted(&with_identity, &scalar_vec).1 < ops);
+
+    // A single term is a double-and-add scalar multiplication: 0b1011 takes
+    // four doublings and three additions.
+    let scalar = <<G1Affine as AffineCurve>::ScalarField as PrimeField>::BigInt::from(0b1011u64);
+    let (res, ops) = multi_scalar_mul_counted(&point_vec[..1], &[scalar]);
+    assert_eq!(res, point_vec[0].mul(scalar));
+    assert_eq!(ops, 7);
+
+    // Its count is the one of an instrumented run of the scalar multiplication.
+    for (base, scalar) in point_vec.iter().zip(&scalar_vec).take(5) {
+        let mut instrumented = OpCount::enabled();
+        assert_eq!(double_and_add(base, scalar, &mut instrumented), base.mul(*scalar));
+        assert_eq!(multi_scalar_mul_counted(&[*base], &[*scalar]), (base.mul(*scalar), instrumented.total()));
+    }
+}
+
+#[test]
+fn test_multi_scalar_mul_counted_exact() {
+    type BigInt = <<G1Affine as AffineCurve>::ScalarField as PrimeField>::BigInt;
+
+    // With 2-bit windows, 14 = 0b11_10 has the digits 2 and 3, and 4 = 0b01_00
+    // the digit 1, in the two lowest of the 128 windows.
+    let (point_vec, _) = generate_msm_inputs(2);
+    let scalars = [BigInt::from(14u64), BigInt::from(4u64)];
+    let mut ops = OpCount::enabled();
+    let mode = ReductionMode::Projective;
+    let res = multi_scalar_mul_iter_counted::<G1Affine, _, _, _>(scalars.iter().zip(&point_vec), 2, mode, &mut ops);
+    assert_eq!(res, point_vec[0].mul(scalars[0]) + point_vec[1].mul(scalars[1]));
+
+    // Three bucket additions, then each window reduces its 3 buckets in 6
+    // additions and adds the result in. The fold adds every window in, and
+    // doubles twice for each window above the lowest.
+    assert_eq!(ops.additions(), 3 + 128 * 7 + 128);
+    assert_eq!(ops.doublings(), 127 * 2);
+    assert_eq!(ops.total(), 1027 + 254);
+}
+
+#[test]
+fn test_multi_scalar_mul_packed16() {
+    let (point_vec, _) = generate_msm_inputs(100);
+    let scalars: Vec<u16> = (0..point_vec.len() as u32).map(|i| (i * 7919 % 65536) as u16).collect();
+    let widened: Vec<_> = scalars.iter().map(|&s| (s as u64).into()).collect();
+    assert_eq!(multi_scalar_mul_packed16(&point_vec, &scalars), multi_scalar_mul(&point_vec, &widened));
+
+    let extremes = [0, 1, u16::MAX];
+    let widened: Vec<_> = extremes.iter().map(|&s| (s as u64).into()).collect();
+    assert_eq!(multi_scalar_mul_packed16(&point_vec, &extremes), multi_scalar_mul(&point_vec, &widened));
+}
+
+#[test]
+fn test_multi_scalar_mul_all_ones() {
+    // Unit scalars all go through the first window's fast path, leaving every
+    // higher window empty.
+    for size in [2, 33, 300] {
+        let (point_vec, _) = generate_msm_inputs(size);
+        let ones = vec![<G1Affine as AffineCurve>::ScalarField::one().into_repr(); size];
+        assert_eq!(multi_scalar_mul(&point_vec, &ones), batch_affine_sum(&point_vec));
+    }
+}
+
+#[cfg(feature = "arena")]
+#[test]
+fn test_multi_scalar_mul_in_arena() {
+    let mut arena = bumpalo::Bump::new();
+    for size in [10, 100] {
+        let (point_vec, scalar_vec) = generate_msm_inputs(size);
+        let res = multi_scalar_mul_in_arena(&point_vec, &scalar_vec, &arena);
+        assert_eq!(res, multi_scalar_mul(&point_vec, &scalar_vec));
+        arena.reset();
+    }
+}
+
+#[test]
+fn test_scalar_digit_matrix() {
+    type Fr = <G1Affine as AffineCurve>::ScalarField;
+
+    let (_, scalar_vec) = generate_msm_inputs(20);
+    for c in [1, 7, 16, 32] {
+        let (digits, num_windows) = scalar_digit_matrix::<Fr>(&scalar_vec, c);
+        assert_eq!(digits.len(), num_windows * scalar_vec.len());
+
+        let shift = Fr::from(1u64 << (c - 1)) * Fr::from(2u64);
+        for (i, scalar) in scalar_vec.iter().enumerate() {
+            let reconstructed = (0..num_windows)
+                .rev()
+                .fold(Fr::zero(), |acc, w| acc * shift + Fr::from(digits[w * scalar_vec.len() + i] as u64));
+            assert_eq!(reconstructed.into_repr(), *scalar);
+        }
+    }
+}
+
+#[test]
+fn test_multi_scalar_mul_single_term() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(10);
+    let base = point_vec[0];
+    let zero = <G1Affine as AffineCurve>::ScalarField::zero().into_repr();
+    let one = <G1Affine as AffineCurve>::ScalarField::one().into_repr();
+    for scalar in scalar_vec.into_iter().chain([zero, one]) {
+        assert_eq!(multi_scalar_mul(&[base], &[scalar]), base.mul(scalar));
+    }
+}
+
+#[test]
+fn test_msm_precomp_serialization() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(50);
+    let precomp = MsmPrecomp::new(&point_vec);
+    let mut bytes = Vec::new();
+    precomp.serialize(&mut bytes).unwrap();
+
+    let restored = MsmPrecomp::<G1Affine>::deserialize(&bytes[..], precomp.window_bits()).unwrap();
+    assert_eq!(restored.len(), precomp.len());
+    assert_eq!(restored.eval(&scalar_vec), precomp.eval(&scalar_vec));
+
+    assert!(MsmPrecomp::<G1Affine>::deserialize(&bytes[..], precomp.window_bits() + 1).is_err());
+
+    // An empty table only fails on a window size buckets can't be allocated for.
+    let empty_table = |c: usize| {
+        let mut bytes = Vec::new();
+        (c as u64).serialize(&mut bytes).unwrap();
+        0u64.serialize(&mut bytes).unwrap();
+        bytes
+    };
+    assert!(MsmPrecomp::<G1Affine>::deserialize(&empty_table(4)[..], 4).unwrap().is_empty());
+    for c in [0, MAX_BUCKET_WINDOW_BITS + 1] {
+        assert!(MsmPrecomp::<G1Affine>::deserialize(&empty_table(c)[..], c).is_err());
+    }
+}
+
+#[test]
+fn test_multi_scalar_mul_identity_bases() {
+    let (mut point_vec, scalar_vec) = generate_msm_inputs(100);
+    for i in (0..point_vec.len()).step_by(3) {
+        point_vec[i] = G1Affine::zero();
+    }
+    let (bases, scalars): (Vec<_>, Vec<_>) = point_vec
+        .iter()
+        .zip(&scalar_vec)
+        .filter(|(b, _)| !is_identity(*b))
+        .map(|(b, s)| (*b, *s))
+        .unzip();
+
+    let expected = multi_scalar_mul(&bases, &scalars);
+    assert_eq!(multi_scalar_mul(&point_vec, &scalar_vec), expected);
+    // Identity bases are still handled by the mixed additions when they aren't
+    // filtered out.
+    assert_eq!(multi_scalar_mul_nofilter(&point_vec, &scalar_vec), expected);
+}
+
+#[test]
+fn test_multi_scalar_mul_diff() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(100);
+    let mut other: Vec<_> = scalar_vec.iter().rev().copied().collect();
+    other[..10].copy_from_slice(&scalar_vec[..10]);
+
+    assert_eq!(
+        multi_scalar_mul_diff(&point_vec, &scalar_vec, &other),
+        multi_scalar_mul(&point_vec, &scalar_vec) - multi_scalar_mul(&point_vec, &other)
+    );
+    assert!(multi_scalar_mul_diff(&point_vec, &scalar_vec, &scalar_vec).is_zero());
+
+    // Unreduced scalars are taken modulo the group order, and the longer
+    // vector is truncated.
+    let mut unreduced = scalar_vec.clone();
+    unreduced[0].add_nocarry(&<<G1Affine as AffineCurve>::ScalarField as PrimeField>::Params::MODULUS);
+    unreduced.push(scalar_vec[0]);
+    assert!(multi_scalar_mul_diff(&point_vec, &unreduced, &scalar_vec).is_zero());
+    assert_eq!(
+        multi_scalar_mul_diff(&point_vec, &unreduced, &other),
+        multi_scalar_mul(&point_vec, &scalar_vec) - multi_scalar_mul(&point_vec, &other)
+    );
+}
+
+#[test]
+fn test_window_digit_full_limb() {
+    let scalar = <<G1Affine as AffineCurve>::ScalarField as PrimeField>::BigInt::new([
+        0x0123_4567_89ab_cdef,
+        u64::MAX,
+        0,
+        0x4000_0000_0000_0001,
+    ]);
+    assert_eq!(window_digit(&scalar, 0, 64), 0x0123_4567_89ab_cdef);
+    assert_eq!(window_digit(&scalar, 64, 64), u64::MAX);
+    assert_eq!(window_digit(&scalar, 32, 64), 0xffff_ffff_0123_4567);
+    assert_eq!(window_digit(&scalar, 192, 64), 0x4000_0000_0000_0001);
+    assert_eq!(window_digit(&scalar, 0, 4), 0xf);
+    // The bits past the last limb read as zero.
+    assert_eq!(window_digit(&scalar, 250, 10), 0x10);
+
+    // Digits this wide can be read, but an MSM can't allocate their buckets.
+    let (point_vec, _) = generate_msm_inputs(1);
+    assert_eq!(
+        multi_scalar_mul_with_window_size(&point_vec, &[scalar], 64),
+        Err(MsmError::InvalidWindowSize { c: 64, max: MAX_BUCKET_WINDOW_BITS })
+    );
+}
+
+#[test]
+fn test_window_digit_wide() {
+    // Bits 60, 100 and 129 fall in the 70-bit window starting at bit 60, which
+    // spans three limbs; bit 130 is just past it.
+    let scalar = <<G1Affine as AffineCurve>::ScalarField as PrimeField>::BigInt::new([1 << 60, 1 << 36, 0b110, 0]);
+    assert_eq!(window_digit_wide(&scalar, 60, 70), 1 | (1 << 40) | (1 << 69));
+    assert_eq!(window_digit_wide(&scalar, 0, 128), (1 << 60) | (1 << 100));
+    assert_eq!(window_digit_wide(&scalar, 36, 64), window_digit(&scalar, 36, 64) as u128);
+    assert_eq!(window_digit_wide(&scalar, 192, 70), 0);
+    // MSM windows crossing a limb boundary are read the same way.
+    assert_eq!(window_digit(&scalar, 60, 10), 1);
+    assert_eq!(window_digit(&scalar, 127, 5), 0b1100);
+}
+
+#[test]
+fn test_window_size_out_of_range_is_rejected() {
+    let (point_vec, scalar_vec) = generate_msm_inputs(4);
+    for c in [0, MAX_BUCKET_WINDOW_BITS + 1, 40, 65] {
+        let err = MsmError::InvalidWindowSize { c, max: MAX_BUCKET_WINDOW_BITS };
+        assert_eq!(multi_scalar_mul_with_window_size(&point_vec, &scalar_vec, c), Err(err));
+        assert_eq!(compute_window_sums(&point_vec, &scalar_vec, c), Err(err));
+        assert_eq!(WindowReducer::<G1Affine>::new(c).err(), Some(err));
+    }
+}
+
+#[test]
+fn test_multi_scalar_mul_small() {
+    let (point_vec, _) = generate_msm_inputs(100);
+    for bits in [1, 3, 10, 33, 64] {
+        let mask = u64::MAX >> (64 - bits);
+        let scalars: Vec<u64> = (0..point_vec.len() as u64)
+            .map(|i| i.wrapping_mul(0x9e37_79b9_7f4a_7c15) & mask)
+            .collect();
+        let widened: Vec<_> = scalars.iter().map(|&s| s.into()).collect();
+        assert_eq!(multi_scalar_mul_small(&point_vec, &scalars, bits), multi_scalar_mul(&point_vec, &widened));
+    }
+}
+
+#[test]
+fn test_dominant_terms() {
+    use ark_ff::Field;
+
+    let (point_vec, _) = generate_msm_inputs(100);
+    let mut scalars: Vec<_> = (0..point_vec.len() as u64).map(|i| (i % 1000).into()).collect();
+    scalars[42] = <G1Affine as AffineCurve>::ScalarField::from(u64::MAX).square().into_repr();
+
+    let dominant = dominant_terms(&point_vec, &scalars, 3);
+    assert_eq!(dominant, vec![42, 99, 98]);
+    assert_eq!(dominant_terms(&point_vec, &scalars, 1000).len(), 100);
+}
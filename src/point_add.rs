@@ -0,0 +1,1194 @@
+use ark_bls12_381::G1Affine;
+use ark_ec::short_weierstrass_jacobian::{GroupAffine, GroupProjective};
+use ark_ec::{AffineCurve, ProjectiveCurve, SWModelParameters};
+use ark_ff::{Field, One, Zero};
+use std::collections::HashMap;
+
+use crate::error::MsmError;
+
+/// Generates `size` random BLS12-381 G1 points and `size` random index pairs
+/// into them, as inputs for the point addition benchmarks.
+pub fn generate_point_add_inputs(size: usize) -> (Vec<G1Affine>, Vec<usize>, Vec<usize>) {
+    use crate::msm::generate_msm_inputs;
+    use ark_std::rand::Rng;
+
+    let (points, _) = generate_msm_inputs(size);
+    let mut rng = ark_std::test_rng();
+    let first_idx = (0..size).map(|_| rng.gen_range(0..size)).collect();
+    let second_idx = (0..size).map(|_| rng.gen_range(0..size)).collect();
+    (points, first_idx, second_idx)
+}
+
+/// Computes `points[first_idx[i]] + points[second_idx[i]]` for every `i`,
+/// one mixed addition at a time.
+///
+/// Equal indices are fine: mixed addition detects the doubling case itself.
+pub fn mixed_point_addition<G: AffineCurve>(
+    points: &[G],
+    first_idx: &[usize],
+    second_idx: &[usize],
+) -> Vec<G::Projective> {
+    let mut out = vec![G::Projective::zero(); first_idx.len()];
+    mixed_point_addition_into(points, first_idx, second_idx, &mut out);
+    out
+}
+
+/// Same as [`mixed_point_addition`], over projective points, which are
+/// normalized to affine once, with a single batched inversion.
+pub fn mixed_point_addition_from_proj<G: AffineCurve>(
+    points: &[G::Projective],
+    first_idx: &[usize],
+    second_idx: &[usize],
+) -> Vec<G::Projective> {
+    let points = G::Projective::batch_normalization_into_affine(points);
+    mixed_point_addition(&points, first_idx, second_idx)
+}
+
+/// Same as [`mixed_point_addition`], overwriting `out` instead of allocating
+/// the results.
+pub fn mixed_point_addition_into<G: AffineCurve>(
+    points: &[G],
+    first_idx: &[usize],
+    second_idx: &[usize],
+    out: &mut [G::Projective],
+) {
+    assert_eq!(first_idx.len(), second_idx.len(), "index vectors must have the same length");
+    assert_eq!(out.len(), first_idx.len(), "one output slot is needed per pair");
+
+    out.iter_mut()
+        .zip(first_idx.iter().zip(second_idx))
+        .for_each(|(r, (&first, &second))| *r = points[first].into_projective().add_mixed(&points[second]));
+}
+
+/// Computes `acc[i] += addends[indices[i]]` for every `i`, with one mixed
+/// addition per accumulator.
+pub fn batch_add_mixed_into<G: AffineCurve>(acc: &mut [G::Projective], addends: &[G], indices: &[usize]) {
+    assert_eq!(acc.len(), indices.len(), "one index is needed per accumulator");
+
+    acc.iter_mut()
+        .zip(indices)
+        .for_each(|(acc, &index)| acc.add_assign_mixed(&addends[index]));
+}
+
+/// Computes `points[first_idx[i]] + points[second_idx[i]]` for every `i` with
+/// the affine chord formula, sharing a single field inversion across the whole
+/// batch (Montgomery's trick).
+///
+/// Pairs the chord formula can't handle (either point at infinity, or equal
+/// `x` coordinates, i.e. a doubling or a negation) fall back to a mixed
+/// addition.
+///
+/// This works over any base field, e.g. `Fp2` for BLS12-381 G2, where the
+/// batched inversion is still element-wise `Field::inverse`. There a mixed
+/// addition costs the same number of (about 3x dearer) field multiplications,
+/// while an inversion is relatively cheaper, so the batch size at which this
+/// overtakes [`mixed_point_addition`] is expected to be lower than on G1, and
+/// the saving per pair larger; `benches/point_add.rs` measures both.
+pub fn batch_affine_point_addition<P: SWModelParameters>(
+    points: &[GroupAffine<P>],
+    first_idx: &[usize],
+    second_idx: &[usize],
+) -> Vec<GroupProjective<P>> {
+    let slopes = chord_slopes(indexed_pairs(points, first_idx, second_idx));
+    add_with_slopes(indexed_pairs(points, first_idx, second_idx), &slopes)
+}
+
+/// Runs two independent [`batch_affine_point_addition`]s, each given as
+/// `(points, first_idx, second_idx)`, sharing a single batched inversion
+/// across both.
+pub fn batch_affine_point_addition_two<P: SWModelParameters>(
+    (points, first_idx, second_idx): (&[GroupAffine<P>], &[usize], &[usize]),
+    (points2, first_idx2, second_idx2): (&[GroupAffine<P>], &[usize], &[usize]),
+) -> (Vec<GroupProjective<P>>, Vec<GroupProjective<P>>) {
+    let pairs = indexed_pairs(points, first_idx, second_idx);
+    let pairs2 = indexed_pairs(points2, first_idx2, second_idx2);
+    let slopes = chord_slopes(pairs.clone().chain(pairs2.clone()));
+
+    let (slopes, slopes2) = slopes.split_at(first_idx.len());
+    (add_with_slopes(pairs, slopes), add_with_slopes(pairs2, slopes2))
+}
+
+/// Same as [`batch_affine_point_addition`], writing the affine sum of the
+/// `i`-th pair into `points[out_idx[i]]` instead of allocating the results, so
+/// that the inputs' storage is reused.
+///
+/// The pairs are processed in order, so the outputs may alias the inputs as
+/// long as:
+/// - the output slots are distinct, and
+/// - no pair reads the output slot of an earlier pair, i.e. `out_idx[i]` is
+///   in neither `first_idx[j..]` nor `second_idx[j..]` for any `j > i`.
+///
+/// Writing each sum over its own first point, or a half of the buffer over
+/// the other, both qualify. Debug builds check the contract.
+pub fn batch_affine_point_addition_in_place<P: SWModelParameters>(
+    points: &mut [GroupAffine<P>],
+    first_idx: &[usize],
+    second_idx: &[usize],
+    out_idx: &[usize],
+) {
+    assert_eq!(out_idx.len(), first_idx.len(), "one output slot is needed per pair");
+    debug_assert!(
+        in_place_contract_holds(points.len(), first_idx, second_idx, out_idx),
+        "in-place outputs must be distinct and not read by later pairs"
+    );
+
+    let slopes = chord_slopes(indexed_pairs(points, first_idx, second_idx));
+
+    // Exceptional sums are written last, normalized together, which is fine
+    // since no later pair reads their slots.
+    let mut exceptional_slots = Vec::new();
+    let mut exceptional_sums = Vec::new();
+    for (i, m) in slopes.into_iter().enumerate() {
+        let (p, q) = (points[first_idx[i]], points[second_idx[i]]);
+        if is_exceptional(&p, &q) {
+            exceptional_slots.push(out_idx[i]);
+            exceptional_sums.push(p.into_projective().add_mixed(&q));
+        } else {
+            points[out_idx[i]] = chord_add(&p, &q, m);
+        }
+    }
+    let exceptional_sums = GroupProjective::batch_normalization_into_affine(&exceptional_sums);
+    for (slot, sum) in exceptional_slots.into_iter().zip(exceptional_sums) {
+        points[slot] = sum;
+    }
+}
+
+/// Checks the aliasing contract of [`batch_affine_point_addition_in_place`],
+/// walking the pairs backwards while tracking the slots read from then on.
+fn in_place_contract_holds(len: usize, first_idx: &[usize], second_idx: &[usize], out_idx: &[usize]) -> bool {
+    let mut read_later = vec![false; len];
+    let mut written = vec![false; len];
+    for i in (0..out_idx.len()).rev() {
+        if read_later[out_idx[i]] || written[out_idx[i]] {
+            return false;
+        }
+        written[out_idx[i]] = true;
+        read_later[first_idx[i]] = true;
+        read_later[second_idx[i]] = true;
+    }
+    true
+}
+
+/// Same as [`batch_affine_point_addition`], trusting the caller that no pair
+/// is exceptional: every pair goes through the chord formula, without the
+/// per-pair fallback checks. Debug builds still assert it, so that misuse is
+/// caught during development.
+///
+/// Release builds don't look for exceptional pairs at all, so a single one
+/// makes the shared inversion fail, and every sum of the batch is then
+/// garbage, not just its own.
+pub fn batch_affine_point_addition_unchecked<P: SWModelParameters>(
+    points: &[GroupAffine<P>],
+    first_idx: &[usize],
+    second_idx: &[usize],
+) -> Vec<GroupProjective<P>> {
+    let pairs = indexed_pairs(points, first_idx, second_idx);
+    debug_assert!(
+        pairs.clone().all(|(p, q)| !is_exceptional(p, q)),
+        "unchecked batch affine addition got a zero denominator or a point at infinity"
+    );
+
+    let d_vec: Vec<P::BaseField> = pairs.clone().map(|(p, q)| q.x - p.x).collect();
+    let inv_vec = batch_inverse_unchecked(&d_vec);
+    pairs
+        .zip(inv_vec)
+        .map(|((p, q), inv)| chord_add(p, q, (q.y - p.y) * inv).into_projective())
+        .collect()
+}
+
+/// Same as [`batch_affine_point_addition`], yielding the sums one at a time
+/// rather than collecting them. The batched inversion needs all the
+/// denominators, so it runs up front; only the additions are lazy.
+pub fn batch_affine_point_addition_iter<'a, P: SWModelParameters>(
+    points: &'a [GroupAffine<P>],
+    first_idx: &'a [usize],
+    second_idx: &'a [usize],
+) -> impl Iterator<Item = GroupProjective<P>> + 'a {
+    let slopes = chord_slopes(indexed_pairs(points, first_idx, second_idx));
+    indexed_pairs(points, first_idx, second_idx)
+        .zip(slopes)
+        .map(|((p, q), m)| add_with_slope(p, q, m))
+}
+
+/// Same as [`batch_affine_point_addition`], spreading the pairs over the rayon
+/// thread pool in one chunk per thread, each with its own batched inversion.
+#[cfg(feature = "parallel")]
+pub fn batch_affine_point_addition_par<P: SWModelParameters>(
+    points: &[GroupAffine<P>],
+    first_idx: &[usize],
+    second_idx: &[usize],
+) -> Vec<GroupProjective<P>> {
+    use rayon::prelude::*;
+
+    assert_eq!(first_idx.len(), second_idx.len(), "index vectors must have the same length");
+    let threads = rayon::current_num_threads();
+    let chunk_size = core::cmp::max(1, (first_idx.len() + threads - 1) / threads);
+
+    first_idx
+        .par_chunks(chunk_size)
+        .zip(second_idx.par_chunks(chunk_size))
+        .flat_map_iter(|(first, second)| batch_affine_point_addition(points, first, second))
+        .collect()
+}
+
+/// Computes `(points[first_idx[i]] + points[second_idx[i]]) * scale` for every
+/// `i`, keeping the sums affine through the scaling: each doubling and addition
+/// of the double-and-add ladder advances the whole batch at once, with a single
+/// batched inversion per step, like the addition itself.
+pub fn batch_affine_point_addition_scaled<P: SWModelParameters>(
+    points: &[GroupAffine<P>],
+    first_idx: &[usize],
+    second_idx: &[usize],
+    scale: u64,
+) -> Vec<GroupProjective<P>> {
+    let sums = affine_sums(indexed_pairs(points, first_idx, second_idx));
+    if scale == 0 {
+        return vec![GroupProjective::zero(); sums.len()];
+    }
+
+    // The top bit of `scale` is the sums themselves.
+    let mut accs = sums.clone();
+    for bit in (0..63 - scale.leading_zeros()).rev() {
+        accs = affine_doublings(&accs);
+        if (scale >> bit) & 1 == 1 {
+            accs = affine_sums(accs.iter().zip(&sums));
+        }
+    }
+    accs.iter().map(|p| p.into_projective()).collect()
+}
+
+/// Same as [`batch_affine_point_addition`], also returning the chord slope
+/// `m_i` used for each pair, so that `y2 - y1 = m_i * (x2 - x1)`.
+///
+/// Exceptional pairs don't go through the chord formula and get a zero slope.
+pub fn batch_affine_point_addition_with_slopes<P: SWModelParameters>(
+    points: &[GroupAffine<P>],
+    first_idx: &[usize],
+    second_idx: &[usize],
+) -> (Vec<GroupProjective<P>>, Vec<P::BaseField>) {
+    let slopes = chord_slopes(indexed_pairs(points, first_idx, second_idx));
+    let sums = add_with_slopes(indexed_pairs(points, first_idx, second_idx), &slopes);
+    (sums, slopes)
+}
+
+/// Same as [`batch_affine_point_addition`], but leaving the exceptional pairs
+/// to the caller: their results are identity placeholders, and their indices
+/// are returned alongside.
+pub fn batch_affine_point_addition_partitioned<P: SWModelParameters>(
+    points: &[GroupAffine<P>],
+    first_idx: &[usize],
+    second_idx: &[usize],
+) -> (Vec<GroupProjective<P>>, Vec<usize>) {
+    let slopes = chord_slopes(indexed_pairs(points, first_idx, second_idx));
+
+    let mut exceptional = Vec::new();
+    let sums = indexed_pairs(points, first_idx, second_idx)
+        .zip(slopes)
+        .enumerate()
+        .map(|(i, ((p, q), m))| {
+            if is_exceptional(p, q) {
+                exceptional.push(i);
+                GroupProjective::zero()
+            } else {
+                chord_add(p, q, m).into_projective()
+            }
+        })
+        .collect();
+    (sums, exceptional)
+}
+
+/// Same as [`batch_affine_point_addition`], but each distinct pair is only
+/// added once and its sum broadcast to all of its occurrences, so repeated
+/// pairs don't pay for their own slot in the batched inversion.
+///
+/// Addition being commutative, `(i, j)` and `(j, i)` count as the same pair.
+pub fn batch_affine_point_addition_dedup<P: SWModelParameters>(
+    points: &[GroupAffine<P>],
+    first_idx: &[usize],
+    second_idx: &[usize],
+) -> Vec<GroupProjective<P>> {
+    let (unique_first, unique_second, slots) = dedup_pairs(first_idx, second_idx);
+    let sums = batch_affine_point_addition(points, &unique_first, &unique_second);
+    slots.into_iter().map(|slot| sums[slot]).collect()
+}
+
+/// Splits the pairs into the distinct ones, and for every original pair the
+/// slot of its distinct representative.
+fn dedup_pairs(first_idx: &[usize], second_idx: &[usize]) -> (Vec<usize>, Vec<usize>, Vec<usize>) {
+    assert_eq!(first_idx.len(), second_idx.len(), "index vectors must have the same length");
+
+    let mut seen = HashMap::new();
+    let mut unique_first = Vec::new();
+    let mut unique_second = Vec::new();
+    let slots = first_idx
+        .iter()
+        .zip(second_idx)
+        .map(|(&first, &second)| {
+            let key = (first.min(second), first.max(second));
+            *seen.entry(key).or_insert_with(|| {
+                unique_first.push(first);
+                unique_second.push(second);
+                unique_first.len() - 1
+            })
+        })
+        .collect();
+    (unique_first, unique_second, slots)
+}
+
+/// Whether `p` is the identity. Every identity check on affine points, here
+/// and in the MSM code, goes through this rather than the encoding of the
+/// point, e.g. the `infinity` flag of `GroupAffine`, whose coordinates are then
+/// meaningless (and not necessarily `(0, 0)`).
+pub(crate) fn is_identity<G: AffineCurve>(p: &G) -> bool {
+    p.is_zero()
+}
+
+/// Whether the chord formula can't be used to add `p` and `q`.
+fn is_exceptional<P: SWModelParameters>(p: &GroupAffine<P>, q: &GroupAffine<P>) -> bool {
+    is_identity(p) || is_identity(q) || p.x == q.x
+}
+
+/// Borrows the `(points[first_idx[i]], points[second_idx[i]])` pairs.
+fn indexed_pairs<'a, P: SWModelParameters>(
+    points: &'a [GroupAffine<P>],
+    first_idx: &'a [usize],
+    second_idx: &'a [usize],
+) -> impl Iterator<Item = (&'a GroupAffine<P>, &'a GroupAffine<P>)> + Clone + 'a {
+    assert_eq!(first_idx.len(), second_idx.len(), "index vectors must have the same length");
+    first_idx
+        .iter()
+        .zip(second_idx)
+        .map(move |(&first, &second)| (&points[first], &points[second]))
+}
+
+/// Computes the chord slope `(y2 - y1) / (x2 - x1)` of every pair with a single
+/// batched inversion, and zero for exceptional pairs.
+fn chord_slopes<'a, P: SWModelParameters>(
+    pairs: impl Iterator<Item = (&'a GroupAffine<P>, &'a GroupAffine<P>)> + Clone,
+) -> Vec<P::BaseField> {
+    // d_vec[i] = x2 - x1, with exceptional pairs replaced by one so that they
+    // don't poison the batched inversion.
+    let d_vec: Vec<P::BaseField> = pairs
+        .clone()
+        .map(|(p, q)| {
+            if is_exceptional(p, q) {
+                P::BaseField::one()
+            } else {
+                q.x - p.x
+            }
+        })
+        .collect();
+
+    let inv_vec = batch_inverse(&d_vec).expect("exceptional denominators are replaced by one");
+
+    pairs
+        .zip(inv_vec)
+        .map(|((p, q), inv)| {
+            if is_exceptional(p, q) {
+                P::BaseField::zero()
+            } else {
+                (q.y - p.y) * inv
+            }
+        })
+        .collect()
+}
+
+/// Inverts every element with a single field inversion (Montgomery's trick),
+/// failing with the index of the first zero element if there is one.
+///
+/// A zero element makes the product of all the elements zero, so zeros are
+/// only looked for once that single inversion fails, rather than up front.
+pub fn batch_inverse<F: Field>(elems: &[F]) -> Result<Vec<F>, MsmError> {
+    if elems.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let (a_vec, product) = prefix_products(elems);
+    match product.inverse() {
+        Some(s) => Ok(inverses_from_product(elems, &a_vec, s)),
+        None => {
+            let index = elems.iter().position(|d| d.is_zero()).unwrap_or(elems.len() - 1);
+            Err(MsmError::NonInvertibleDenominator { index })
+        }
+    }
+}
+
+/// Same as [`batch_inverse`], without any zero handling: a zero element makes
+/// every inverse come out as zero.
+fn batch_inverse_unchecked<F: Field>(elems: &[F]) -> Vec<F> {
+    let (a_vec, product) = prefix_products(elems);
+    inverses_from_product(elems, &a_vec, product.inverse().unwrap_or_else(F::zero))
+}
+
+/// Computes `a_vec[i] = elems[0] * ... * elems[i - 1]`, and the product of all
+/// the elements.
+fn prefix_products<F: Field>(elems: &[F]) -> (Vec<F>, F) {
+    let mut a_vec = Vec::with_capacity(elems.len());
+    let mut acc = F::one();
+    for d in elems {
+        a_vec.push(acc);
+        acc *= d;
+    }
+    (a_vec, acc)
+}
+
+/// Given `s`, the inverse of the product of all the elements, walks backwards
+/// with `s * a_vec[i] = elems[i]^{-1}`.
+fn inverses_from_product<F: Field>(elems: &[F], a_vec: &[F], mut s: F) -> Vec<F> {
+    let mut inv_vec = vec![F::one(); elems.len()];
+    for i in (0..elems.len()).rev() {
+        inv_vec[i] = s * a_vec[i];
+        s *= elems[i];
+    }
+    inv_vec
+}
+
+/// Same as [`batch_inverse`], inverting the nonzero elements with a single
+/// field inversion and leaving `None` for the zero ones, whose indices are
+/// also returned, in increasing order.
+pub fn batch_inverse_lenient<F: Field>(elems: &[F]) -> (Vec<Option<F>>, Vec<usize>) {
+    let (zeros, nonzero): (Vec<_>, Vec<_>) = elems.iter().enumerate().partition(|(_, e)| e.is_zero());
+    let nonzero: Vec<F> = nonzero.into_iter().map(|(_, e)| *e).collect();
+    let mut inverses = batch_inverse(&nonzero)
+        .expect("zeros were left out")
+        .into_iter();
+
+    let inverses = elems
+        .iter()
+        .map(|e| if e.is_zero() { None } else { inverses.next() })
+        .collect();
+    (inverses, zeros.into_iter().map(|(i, _)| i).collect())
+}
+
+/// Adds every pair using its precomputed chord slope, falling back to a mixed
+/// addition for exceptional pairs.
+fn add_with_slopes<'a, P: SWModelParameters>(
+    pairs: impl Iterator<Item = (&'a GroupAffine<P>, &'a GroupAffine<P>)>,
+    slopes: &[P::BaseField],
+) -> Vec<GroupProjective<P>> {
+    pairs.zip(slopes).map(|((p, q), &m)| add_with_slope(p, q, m)).collect()
+}
+
+/// Adds `p` and `q` using the chord slope `m`, or with a mixed addition if the
+/// pair is exceptional.
+fn add_with_slope<P: SWModelParameters>(p: &GroupAffine<P>, q: &GroupAffine<P>, m: P::BaseField) -> GroupProjective<P> {
+    if is_exceptional(p, q) {
+        return p.into_projective().add_mixed(q);
+    }
+    chord_add(p, q, m).into_projective()
+}
+
+/// Builds the finite affine point `(x, y)` through the public constructor, so
+/// that the batch addition code doesn't depend on the layout of `GroupAffine`.
+/// The point is not checked to be on the curve.
+pub fn affine_from_xy<P: SWModelParameters>(x: P::BaseField, y: P::BaseField) -> GroupAffine<P> {
+    GroupAffine::new(x, y, false)
+}
+
+/// Adds two points with distinct `x` coordinates given the slope `m` of the
+/// chord through them.
+fn chord_add<P: SWModelParameters>(p: &GroupAffine<P>, q: &GroupAffine<P>, m: P::BaseField) -> GroupAffine<P> {
+    let x = m.square() - p.x - q.x;
+    let y = m * (p.x - x) - p.y;
+    let r = affine_from_xy(x, y);
+
+    #[cfg(feature = "validate-outputs")]
+    validate_output(&r);
+
+    r
+}
+
+/// Computes `points[i] + points[i + n / 2]` for every `i` of the first half,
+/// like [`batch_affine_point_addition`] without building index vectors.
+pub fn batch_affine_halves_add<P: SWModelParameters>(points: &[GroupAffine<P>]) -> Vec<GroupProjective<P>> {
+    assert!(points.len() % 2 == 0, "halves addition needs an even number of points");
+
+    let (lo, hi) = points.split_at(points.len() / 2);
+    let slopes = chord_slopes(lo.iter().zip(hi));
+    add_with_slopes(lo.iter().zip(hi), &slopes)
+}
+
+/// Sums all the points with rounds of pairwise batched affine additions, so
+/// that each of the `log2(n)` rounds costs a single inversion.
+pub fn batch_affine_sum<P: SWModelParameters>(points: &[GroupAffine<P>]) -> GroupProjective<P> {
+    let mut level = points.to_vec();
+    while level.len() > 1 {
+        level = batch_affine_pairwise_sum(&level);
+    }
+    level.first().map_or_else(GroupProjective::zero, |p| p.into_projective())
+}
+
+/// Computes `points[2i] + points[2i + 1]` in affine coordinates, carrying an
+/// odd last point over as is.
+fn batch_affine_pairwise_sum<P: SWModelParameters>(points: &[GroupAffine<P>]) -> Vec<GroupAffine<P>> {
+    let mut sums = affine_sums(points.chunks_exact(2).map(|pair| (&pair[0], &pair[1])));
+    if points.len() % 2 == 1 {
+        sums.push(points[points.len() - 1]);
+    }
+    sums
+}
+
+/// Sums `points` as `window` interleaved chains, the `j`-th one summing
+/// `points[j], points[j + window], ...`: each step advances all the chains at
+/// once, with one batched inversion per `window` additions rather than one per
+/// addition. The chains are then summed together with [`batch_affine_sum`].
+pub fn batch_affine_chain_sum<P: SWModelParameters>(points: &[GroupAffine<P>], window: usize) -> GroupProjective<P> {
+    assert!(window > 0, "chain sum window must be positive");
+
+    let (first, rest) = points.split_at(core::cmp::min(window, points.len()));
+    let mut accs = first.to_vec();
+    for chunk in rest.chunks(window) {
+        let sums = affine_sums(accs.iter().zip(chunk));
+        accs[..sums.len()].copy_from_slice(&sums);
+    }
+    batch_affine_sum(&accs)
+}
+
+/// Adds every pair in affine coordinates with a single batched inversion.
+fn affine_sums<'a, P: SWModelParameters>(
+    pairs: impl Iterator<Item = (&'a GroupAffine<P>, &'a GroupAffine<P>)> + Clone,
+) -> Vec<GroupAffine<P>> {
+    let slopes = chord_slopes(pairs.clone());
+
+    let mut sums = Vec::with_capacity(slopes.len() + 1);
+    // Exceptional pairs are rare, so their projective sums are normalized
+    // together at the end.
+    let mut exceptional_slots = Vec::new();
+    let mut exceptional_sums = Vec::new();
+    for (i, ((p, q), m)) in pairs.zip(slopes).enumerate() {
+        if is_exceptional(p, q) {
+            exceptional_slots.push(i);
+            exceptional_sums.push(p.into_projective().add_mixed(q));
+            sums.push(GroupAffine::zero());
+        } else {
+            sums.push(chord_add(p, q, m));
+        }
+    }
+    let exceptional_sums = GroupProjective::batch_normalization_into_affine(&exceptional_sums);
+    for (slot, sum) in exceptional_slots.into_iter().zip(exceptional_sums) {
+        sums[slot] = sum;
+    }
+    sums
+}
+
+/// Doubles every point in affine coordinates with a single batched inversion,
+/// using the tangent slope `(3x^2 + a) / 2y`.
+fn affine_doublings<P: SWModelParameters>(points: &[GroupAffine<P>]) -> Vec<GroupAffine<P>> {
+    // The identity and the points of order two (`y = 0`) have no tangent, and
+    // double to the identity.
+    let is_exceptional = |p: &GroupAffine<P>| is_identity(p) || p.y.is_zero();
+
+    let d_vec: Vec<P::BaseField> = points
+        .iter()
+        .map(|p| if is_exceptional(p) { P::BaseField::one() } else { p.y.double() })
+        .collect();
+    let inv_vec = batch_inverse(&d_vec).expect("exceptional denominators are replaced by one");
+
+    points
+        .iter()
+        .zip(inv_vec)
+        .map(|(p, inv)| {
+            if is_exceptional(p) {
+                GroupAffine::zero()
+            } else {
+                let x_squared = p.x.square();
+                chord_add(p, p, (x_squared.double() + x_squared + P::COEFF_A) * inv)
+            }
+        })
+        .collect()
+}
+
+/// Catches formula regressions early by checking a produced point is on the
+/// curve, in release builds too: the check is the whole point of the feature.
+#[cfg(feature = "validate-outputs")]
+fn validate_output<P: SWModelParameters>(p: &GroupAffine<P>) {
+    assert!(p.is_on_curve(), "batch affine addition produced a point off the curve");
+}
+
+/// Runs every addition backend over the same pairs, and checks that their sums
+/// all agree with [`mixed_point_addition`] once normalized to affine.
+#[cfg(test)]
+pub(crate) fn assert_all_addition_backends_agree<P: SWModelParameters>(
+    points: &[GroupAffine<P>],
+    first_idx: &[usize],
+    second_idx: &[usize],
+) {
+    let normalize = |sums: &[GroupProjective<P>]| GroupProjective::batch_normalization_into_affine(sums);
+    let expected = normalize(&mixed_point_addition(points, first_idx, second_idx));
+
+    let mut into = vec![GroupProjective::zero(); first_idx.len()];
+    mixed_point_addition_into(points, first_idx, second_idx, &mut into);
+
+    let mut accumulated: Vec<_> = first_idx.iter().map(|&i| points[i].into_projective()).collect();
+    batch_add_mixed_into(&mut accumulated, points, second_idx);
+
+    // The partitioned backend leaves its exceptional pairs to the caller.
+    let (mut partitioned, exceptional) = batch_affine_point_addition_partitioned(points, first_idx, second_idx);
+    for &i in &exceptional {
+        partitioned[i] = points[first_idx[i]].into_projective().add_mixed(&points[second_idx[i]]);
+    }
+
+    #[allow(unused_mut)]
+    let mut backends = vec![
+        ("mixed_point_addition_into", into),
+        ("batch_add_mixed_into", accumulated),
+        ("batch_affine_point_addition", batch_affine_point_addition(points, first_idx, second_idx)),
+        (
+            "batch_affine_point_addition_iter",
+            batch_affine_point_addition_iter(points, first_idx, second_idx).collect(),
+        ),
+        (
+            "batch_affine_point_addition_with_slopes",
+            batch_affine_point_addition_with_slopes(points, first_idx, second_idx).0,
+        ),
+        ("batch_affine_point_addition_partitioned", partitioned),
+        ("batch_affine_point_addition_dedup", batch_affine_point_addition_dedup(points, first_idx, second_idx)),
+        (
+            "batch_affine_point_addition_scaled",
+            batch_affine_point_addition_scaled(points, first_idx, second_idx, 1),
+        ),
+        (
+            "batch_affine_point_addition_two",
+            batch_affine_point_addition_two((points, first_idx, second_idx), (points, &[], &[])).0,
+        ),
+    ];
+    #[cfg(feature = "parallel")]
+    backends.push(("batch_affine_point_addition_par", batch_affine_point_addition_par(points, first_idx, second_idx)));
+    if exceptional.is_empty() {
+        backends.push((
+            "batch_affine_point_addition_unchecked",
+            batch_affine_point_addition_unchecked(points, first_idx, second_idx),
+        ));
+    }
+
+    for (name, sums) in backends {
+        assert_eq!(normalize(&sums), expected, "{} disagrees with mixed_point_addition", name);
+    }
+}
+
+/// Checks the addition backends agree on `P` over random, distinct-x,
+/// doubling, repeated and negation/identity index patterns.
+#[cfg(test)]
+fn check_addition_backends<P: SWModelParameters>() {
+    use crate::msm::random_subgroup_points;
+    use ark_std::rand::Rng;
+
+    let size = 64;
+    let mut rng = ark_std::test_rng();
+    let mut points: Vec<GroupAffine<P>> = random_subgroup_points(size, &mut rng);
+
+    let random_first: Vec<usize> = (0..size).map(|_| rng.gen_range(0..size)).collect();
+    let random_second: Vec<usize> = (0..size).map(|_| rng.gen_range(0..size)).collect();
+    assert_all_addition_backends_agree(&points, &random_first, &random_second);
+
+    let lo: Vec<usize> = (0..size / 2).collect();
+    let hi: Vec<usize> = (size / 2..size).collect();
+    assert_all_addition_backends_agree(&points, &lo, &hi);
+    assert_all_addition_backends_agree(&points, &lo, &lo);
+
+    let repeated: Vec<usize> = (0..size).map(|i| i % 4).collect();
+    let repeated_rev: Vec<usize> = (0..size).map(|i| 4 + i % 3).collect();
+    assert_all_addition_backends_agree(&points, &repeated, &repeated_rev);
+
+    points[1] = -points[0];
+    points[2] = GroupAffine::zero();
+    assert_all_addition_backends_agree(&points, &[0, 1, 2, 2, 3, 0], &[1, 0, 5, 2, 2, 0]);
+}
+
+#[test]
+fn test_batch_affine_point_addition() {
+    use crate::msm::generate_msm_inputs;
+    use ark_std::rand::Rng;
+
+    let size = 1 << 8;
+    let (mut points, _) = generate_msm_inputs(size);
+    points[1] = -points[0];
+    points[2] = GroupAffine::zero();
+
+    let mut rng = ark_std::test_rng();
+    let mut first_idx: Vec<usize> = (0..size).map(|_| rng.gen_range(0..size)).collect();
+    let mut second_idx: Vec<usize> = (0..size).map(|_| rng.gen_range(0..size)).collect();
+    // Doubling, negation and identity pairs.
+    first_idx[..3].copy_from_slice(&[3, 0, 2]);
+    second_idx[..3].copy_from_slice(&[3, 1, 4]);
+
+    let expected = mixed_point_addition(&points, &first_idx, &second_idx);
+    let res = batch_affine_point_addition(&points, &first_idx, &second_idx);
+    assert_eq!(res, expected);
+}
+
+#[cfg(feature = "validate-outputs")]
+#[test]
+#[should_panic(expected = "off the curve")]
+fn test_validate_output_rejects_corrupted_point() {
+    use crate::msm::generate_msm_inputs;
+
+    let (points, _) = generate_msm_inputs(2);
+    let mut corrupted = points[0];
+    corrupted.y.double_in_place();
+    validate_output(&corrupted);
+}
+
+#[cfg(feature = "validate-outputs")]
+#[test]
+#[should_panic(expected = "off the curve")]
+fn test_validate_output_rejects_wrong_slope() {
+    use crate::msm::generate_msm_inputs;
+
+    let (points, _) = generate_msm_inputs(2);
+    let (p, q) = (&points[0], &points[1]);
+    let slope = (q.y - p.y) * (q.x - p.x).inverse().unwrap();
+    assert!(chord_add(p, q, slope).is_on_curve());
+    chord_add(p, q, slope + ark_bls12_381::Fq::one());
+}
+
+#[test]
+fn test_generate_point_add_inputs() {
+    let (points, first_idx, second_idx) = generate_point_add_inputs(1 << 6);
+    assert_eq!(points.len(), 1 << 6);
+    assert_eq!(
+        batch_affine_point_addition(&points, &first_idx, &second_idx),
+        mixed_point_addition(&points, &first_idx, &second_idx)
+    );
+}
+
+#[test]
+fn test_batch_affine_point_addition_g2() {
+    use crate::msm::random_subgroup_points;
+    use ark_bls12_381::G2Affine;
+    use ark_std::rand::Rng;
+
+    let size = 1 << 7;
+    let mut rng = ark_std::test_rng();
+    let mut points: Vec<G2Affine> = random_subgroup_points(size, &mut rng);
+    points[1] = -points[0];
+    points[2] = GroupAffine::zero();
+
+    let mut first_idx: Vec<usize> = (0..size).map(|_| rng.gen_range(0..size)).collect();
+    let mut second_idx: Vec<usize> = (0..size).map(|_| rng.gen_range(0..size)).collect();
+    // Doubling, negation and identity pairs.
+    first_idx[..3].copy_from_slice(&[3, 0, 2]);
+    second_idx[..3].copy_from_slice(&[3, 1, 4]);
+
+    let expected = mixed_point_addition(&points, &first_idx, &second_idx);
+    let res = batch_affine_point_addition(&points, &first_idx, &second_idx);
+    assert_eq!(res, expected);
+    assert!(res.iter().all(|p| p.into_affine().is_on_curve()));
+}
+
+#[test]
+fn test_mixed_point_addition_from_proj() {
+    use ark_bls12_381::G1Projective;
+
+    let (points, first_idx, second_idx) = generate_point_add_inputs(1 << 6);
+    let mut projective: Vec<G1Projective> = points.iter().map(|p| p.into_projective()).collect();
+    // Rescale some points, so that they are not already normalized.
+    projective.iter_mut().step_by(3).for_each(|p| p.double_in_place());
+    let normalized: Vec<_> = projective.iter().map(|p| p.into_affine()).collect();
+
+    assert_eq!(
+        mixed_point_addition_from_proj::<G1Affine>(&projective, &first_idx, &second_idx),
+        mixed_point_addition(&normalized, &first_idx, &second_idx)
+    );
+}
+
+#[test]
+fn test_mixed_point_addition_into() {
+    use crate::msm::generate_msm_inputs;
+
+    let (points, _) = generate_msm_inputs(32);
+    let first_idx: Vec<usize> = (0..32).collect();
+    let second_idx: Vec<usize> = (0..32).rev().collect();
+
+    let expected = mixed_point_addition(&points, &first_idx, &second_idx);
+    // Stale contents must be overwritten, not accumulated into.
+    let mut out: Vec<_> = points.iter().map(|p| p.into_projective()).collect();
+    mixed_point_addition_into(&points, &first_idx, &second_idx, &mut out);
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn test_batch_affine_point_addition_with_slopes() {
+    use crate::msm::generate_msm_inputs;
+
+    let (mut points, _) = generate_msm_inputs(64);
+    points[1] = points[0];
+    let first_idx: Vec<usize> = (0..32).collect();
+    let mut second_idx: Vec<usize> = (32..64).collect();
+    // A doubling, which takes the exceptional path.
+    second_idx[0] = 1;
+
+    let (sums, slopes) = batch_affine_point_addition_with_slopes(&points, &first_idx, &second_idx);
+    assert_eq!(sums, batch_affine_point_addition(&points, &first_idx, &second_idx));
+    for ((&first, &second), m) in first_idx.iter().zip(&second_idx).zip(slopes) {
+        let (p, q) = (&points[first], &points[second]);
+        if is_exceptional(p, q) {
+            assert!(m.is_zero());
+        } else {
+            assert_eq!(q.y - p.y, m * (q.x - p.x));
+        }
+    }
+}
+
+#[test]
+fn test_batch_affine_point_addition_dedup() {
+    use crate::msm::generate_msm_inputs;
+
+    let (points, _) = generate_msm_inputs(64);
+    // The second half repeats the first one, half of it with swapped operands.
+    let mut first_idx: Vec<usize> = (0..32).collect();
+    let mut second_idx: Vec<usize> = (32..64).collect();
+    first_idx.extend((0..32).map(|i| if i % 2 == 0 { i } else { i + 32 }));
+    second_idx.extend((0..32).map(|i| if i % 2 == 0 { i + 32 } else { i }));
+
+    let (unique_first, _, _) = dedup_pairs(&first_idx, &second_idx);
+    assert_eq!(unique_first.len(), 32);
+    assert_eq!(
+        batch_affine_point_addition_dedup(&points, &first_idx, &second_idx),
+        mixed_point_addition(&points, &first_idx, &second_idx),
+    );
+}
+
+#[test]
+fn test_batch_affine_sum() {
+    use crate::msm::generate_msm_inputs;
+
+    for size in [0, 1, 2, 3, 7, 64, 101] {
+        let (mut points, _) = generate_msm_inputs(size + 4);
+        points.truncate(size);
+        if size > 3 {
+            points[1] = GroupAffine::zero();
+            points[2] = points[3];
+            points[5] = -points[4];
+        }
+
+        let expected = points.iter().fold(GroupProjective::zero(), |acc, p| acc + p.into_projective());
+        assert_eq!(batch_affine_sum(&points), expected);
+    }
+}
+
+#[test]
+fn test_mixed_point_addition_doubling() {
+    use crate::msm::generate_msm_inputs;
+
+    let (points, _) = generate_msm_inputs(16);
+    let idx: Vec<usize> = (0..16).collect();
+    let res = mixed_point_addition(&points, &idx, &idx);
+    for (r, p) in res.iter().zip(&points) {
+        assert_eq!(*r, p.into_projective().double());
+    }
+}
+
+#[test]
+fn test_batch_inverse() {
+    use ark_bls12_381::Fq;
+    use ark_ff::UniformRand;
+
+    let mut rng = ark_std::test_rng();
+    let mut elems: Vec<Fq> = (0..100).map(|_| Fq::rand(&mut rng)).collect();
+    let inverses = batch_inverse(&elems).unwrap();
+    for (e, inv) in elems.iter().zip(inverses) {
+        assert_eq!(*e * inv, Fq::one());
+    }
+    assert_eq!(batch_inverse::<Fq>(&[]), Ok(Vec::new()));
+
+    elems[42] = Fq::zero();
+    elems[57] = Fq::zero();
+    assert_eq!(batch_inverse(&elems), Err(MsmError::NonInvertibleDenominator { index: 42 }));
+}
+
+#[test]
+fn test_batch_inverse_unchecked() {
+    use ark_bls12_381::Fq;
+
+    let elems: Vec<Fq> = (1..10u64).map(Fq::from).collect();
+    assert_eq!(batch_inverse_unchecked(&elems), batch_inverse(&elems).unwrap());
+
+    // A single zero takes every other inverse down with it.
+    let mut elems = elems;
+    elems[4] = Fq::zero();
+    assert!(batch_inverse_unchecked(&elems).iter().all(|inv| inv.is_zero()));
+}
+
+#[test]
+fn test_batch_inverse_lenient() {
+    use ark_bls12_381::Fq;
+
+    let elems: Vec<Fq> = (0..10u64).map(|i| if i % 3 == 0 { Fq::zero() } else { Fq::from(i) }).collect();
+    let (inverses, zeros) = batch_inverse_lenient(&elems);
+    assert_eq!(zeros, vec![0, 3, 6, 9]);
+    for (e, inv) in elems.iter().zip(&inverses) {
+        assert_eq!(*inv, e.inverse());
+    }
+
+    assert_eq!(batch_inverse_lenient::<Fq>(&[]), (vec![], vec![]));
+}
+
+#[test]
+fn test_batch_inverse_zero_product() {
+    use ark_bls12_381::Fq;
+
+    // A zero anywhere, including the last element which only enters the
+    // product at the very end, makes the single inversion fail.
+    let elems = [Fq::from(3u64), Fq::from(5u64), Fq::zero()];
+    assert_eq!(batch_inverse(&elems), Err(MsmError::NonInvertibleDenominator { index: 2 }));
+    assert_eq!(batch_inverse(&[Fq::zero()]), Err(MsmError::NonInvertibleDenominator { index: 0 }));
+}
+
+#[test]
+fn test_batch_affine_halves_add() {
+    use crate::msm::generate_msm_inputs;
+
+    let (mut points, _) = generate_msm_inputs(64);
+    points[32] = points[0];
+    points[33] = -points[1];
+    let res = batch_affine_halves_add(&points);
+    assert_eq!(res.len(), 32);
+    for (i, r) in res.iter().enumerate() {
+        assert_eq!(*r, points[i].into_projective() + points[i + 32].into_projective());
+    }
+}
+
+#[test]
+fn test_batch_affine_point_addition_partitioned() {
+    use crate::msm::generate_msm_inputs;
+
+    let (mut points, _) = generate_msm_inputs(16);
+    points[1] = -points[0];
+    points[2] = GroupAffine::zero();
+    // Normal, negation, doubling, normal, identity.
+    let first_idx = [4, 0, 3, 5, 2];
+    let second_idx = [5, 1, 3, 6, 7];
+
+    let (sums, exceptional) = batch_affine_point_addition_partitioned(&points, &first_idx, &second_idx);
+    assert_eq!(exceptional, vec![1, 2, 4]);
+    let expected = mixed_point_addition(&points, &first_idx, &second_idx);
+    for (i, sum) in sums.iter().enumerate() {
+        if exceptional.contains(&i) {
+            assert!(sum.is_zero());
+        } else {
+            assert_eq!(*sum, expected[i]);
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_batch_affine_point_addition_par() {
+    use crate::msm::generate_msm_inputs;
+    use ark_std::rand::Rng;
+
+    let size = 1 << 12;
+    let (mut points, _) = generate_msm_inputs(size);
+    points[1] = -points[0];
+    points[2] = GroupAffine::zero();
+
+    let mut rng = ark_std::test_rng();
+    let mut first_idx: Vec<usize> = (0..size).map(|_| rng.gen_range(0..size)).collect();
+    let mut second_idx: Vec<usize> = (0..size).map(|_| rng.gen_range(0..size)).collect();
+    first_idx[..3].copy_from_slice(&[3, 0, 2]);
+    second_idx[..3].copy_from_slice(&[3, 1, 4]);
+
+    assert_eq!(
+        batch_affine_point_addition_par(&points, &first_idx, &second_idx),
+        batch_affine_point_addition(&points, &first_idx, &second_idx),
+    );
+}
+
+#[test]
+fn test_batch_affine_point_addition_scaled() {
+    use crate::msm::generate_msm_inputs;
+    use ark_bls12_381::Fr;
+    use ark_ff::PrimeField;
+
+    let (mut points, _) = generate_msm_inputs(32);
+    points[17] = -points[1];
+    let first_idx: Vec<usize> = (0..16).collect();
+    let mut second_idx: Vec<usize> = (16..32).collect();
+    // A negation, whose identity sum goes through the whole ladder, and a
+    // doubling.
+    second_idx[2] = 2;
+    let sums = batch_affine_point_addition(&points, &first_idx, &second_idx);
+
+    for scale in [0u64, 1, 2, 7, u64::MAX] {
+        let scaled = batch_affine_point_addition_scaled(&points, &first_idx, &second_idx, scale);
+        for (r, sum) in scaled.iter().zip(&sums) {
+            assert_eq!(*r, sum.mul(Fr::from(scale).into_repr()));
+        }
+    }
+}
+
+#[test]
+fn test_batch_add_mixed_into() {
+    use crate::msm::generate_msm_inputs;
+
+    let (points, _) = generate_msm_inputs(32);
+    let indices: Vec<usize> = (0..16).map(|i| (i * 7) % 32).collect();
+    let mut acc: Vec<_> = points[16..].iter().map(|p| p.into_projective()).collect();
+
+    let mut expected = acc.clone();
+    for (acc, &index) in expected.iter_mut().zip(&indices) {
+        acc.add_assign_mixed(&points[index]);
+    }
+    batch_add_mixed_into(&mut acc, &points, &indices);
+    assert_eq!(acc, expected);
+}
+
+#[test]
+fn test_affine_from_xy() {
+    use ark_bls12_381::g1::{Parameters, G1_GENERATOR_X, G1_GENERATOR_Y};
+
+    let p = affine_from_xy::<Parameters>(G1_GENERATOR_X, G1_GENERATOR_Y);
+    assert!(p.is_on_curve());
+    assert!(!is_identity(&p));
+    assert_eq!(p, ark_bls12_381::G1Affine::prime_subgroup_generator());
+}
+
+#[test]
+fn test_batch_affine_point_addition_unchecked() {
+    use crate::msm::generate_msm_inputs;
+
+    let (points, _) = generate_msm_inputs(64);
+    let first_idx: Vec<usize> = (0..32).collect();
+    let second_idx: Vec<usize> = (32..64).collect();
+
+    let expected = mixed_point_addition(&points, &first_idx, &second_idx);
+    let res = batch_affine_point_addition_unchecked(&points, &first_idx, &second_idx);
+    assert_eq!(res, expected);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "zero denominator")]
+fn test_batch_affine_point_addition_unchecked_rejects_doubling() {
+    use crate::msm::generate_msm_inputs;
+
+    let (points, _) = generate_msm_inputs(4);
+    batch_affine_point_addition_unchecked(&points, &[0, 1], &[2, 1]);
+}
+
+#[test]
+fn test_is_identity() {
+    fn check<P: SWModelParameters>() {
+        // The flag alone makes the identity, whatever the coordinates...
+        let g = GroupAffine::<P>::prime_subgroup_generator();
+        assert!(is_identity(&GroupAffine::<P>::new(g.x, g.y, true)));
+        // ...and `(0, 0)` without it is a finite (off-curve) point.
+        let origin = GroupAffine::<P>::new(P::BaseField::zero(), P::BaseField::zero(), false);
+        assert!(!is_identity(&origin));
+    }
+
+    check::<ark_bls12_381::g1::Parameters>();
+    check::<ark_bls12_381::g2::Parameters>();
+    check::<ark_bn254::g1::Parameters>();
+}
+
+#[test]
+fn test_batch_affine_point_addition_two() {
+    use crate::msm::generate_msm_inputs;
+
+    let (mut points, _) = generate_msm_inputs(32);
+    let (points2, _) = generate_msm_inputs(16);
+    points[1] = -points[0];
+
+    let first_idx: Vec<usize> = (0..32).collect();
+    let second_idx: Vec<usize> = (0..32).map(|i| (i * 5 + 1) % 32).collect();
+    let first_idx2: Vec<usize> = (0..8).collect();
+    let second_idx2: Vec<usize> = (8..16).collect();
+
+    let res = batch_affine_point_addition_two(
+        (&points, &first_idx, &second_idx),
+        (&points2, &first_idx2, &second_idx2),
+    );
+    assert_eq!(
+        res,
+        (
+            batch_affine_point_addition(&points, &first_idx, &second_idx),
+            batch_affine_point_addition(&points2, &first_idx2, &second_idx2),
+        )
+    );
+}
+
+#[test]
+fn test_all_addition_backends_agree_bls12_381_g1() {
+    check_addition_backends::<ark_bls12_381::g1::Parameters>();
+}
+
+#[test]
+fn test_all_addition_backends_agree_bls12_381_g2() {
+    check_addition_backends::<ark_bls12_381::g2::Parameters>();
+}
+
+#[test]
+fn test_all_addition_backends_agree_bn254_g1() {
+    check_addition_backends::<ark_bn254::g1::Parameters>();
+}
+
+#[test]
+fn test_batch_affine_chain_sum() {
+    use crate::msm::generate_msm_inputs;
+
+    let (mut points, _) = generate_msm_inputs(100);
+    // Make the chains run into a doubling, a negation and the identity.
+    points[8] = points[0];
+    points[9] = -points[1];
+    points[10] = GroupAffine::zero();
+
+    let expected = points.iter().fold(GroupProjective::zero(), |acc, p| acc.add_mixed(p));
+    for window in [1, 4, 8, 99, 100, 200] {
+        assert_eq!(batch_affine_chain_sum(&points, window), expected);
+    }
+    assert!(batch_affine_chain_sum::<ark_bls12_381::g1::Parameters>(&[], 4).is_zero());
+}
+
+#[test]
+fn test_batch_affine_point_addition_iter() {
+    use crate::msm::generate_msm_inputs;
+
+    let (mut points, _) = generate_msm_inputs(32);
+    points[1] = -points[0];
+    let first_idx: Vec<usize> = (0..32).collect();
+    let second_idx: Vec<usize> = (0..32).map(|i| (i * 3 + 1) % 32).collect();
+
+    let collected: Vec<_> = batch_affine_point_addition_iter(&points, &first_idx, &second_idx).collect();
+    assert_eq!(collected, batch_affine_point_addition(&points, &first_idx, &second_idx));
+}
+
+#[test]
+fn test_batch_affine_point_addition_in_place() {
+    use crate::msm::generate_msm_inputs;
+
+    let (mut points, _) = generate_msm_inputs(64);
+    points[33] = -points[1];
+    points[34] = points[2];
+    let lo: Vec<usize> = (0..32).collect();
+    let hi: Vec<usize> = (32..64).collect();
+    let expected = GroupProjective::batch_normalization_into_affine(&batch_affine_point_addition(&points, &lo, &hi));
+
+    // The upper half of the buffer is scratch, summed into the lower half.
+    batch_affine_point_addition_in_place(&mut points, &lo, &hi, &lo);
+    assert_eq!(&points[..32], &expected[..]);
+
+    // Repeatedly halving in place sums the whole buffer into its first slot.
+    let (mut points, _) = generate_msm_inputs(64);
+    let expected = batch_affine_sum(&points);
+    let mut len = points.len();
+    while len > 1 {
+        let half: Vec<usize> = (0..len / 2).collect();
+        let upper: Vec<usize> = (len / 2..len).collect();
+        batch_affine_point_addition_in_place(&mut points, &half, &upper, &half);
+        len /= 2;
+    }
+    assert_eq!(points[0].into_projective(), expected);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "not read by later pairs")]
+fn test_batch_affine_point_addition_in_place_rejects_aliasing() {
+    use crate::msm::generate_msm_inputs;
+
+    // The second pair would read the output of the first one.
+    let (mut points, _) = generate_msm_inputs(4);
+    batch_affine_point_addition_in_place(&mut points, &[0, 2], &[1, 0], &[0, 3]);
+}
@@ -0,0 +1,116 @@
+// Property-based tests over random small-to-medium inputs, with many zero and
+// unit scalars and repeated bases, run from a fixed seed so that every run
+// checks the same cases. A failing case is shrunk to a minimal one.
+
+use ark_bls12_381::{g1, Fr, G1Affine};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{BigInteger256, Field, One, PrimeField, Zero};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::test_runner::{Config, RngAlgorithm, TestRng, TestRunner};
+
+use crate::msm::{
+    multi_scalar_mul, multi_scalar_mul_nofilter, multi_scalar_mul_with_reduction, naive_msm, MsmPrecomp,
+    ReductionMode,
+};
+use crate::point_add::{assert_all_addition_backends_agree, batch_inverse_lenient};
+
+fn runner() -> TestRunner {
+    let config = Config {
+        cases: 32,
+        failure_persistence: None,
+        ..Config::default()
+    };
+    TestRunner::new_with_rng(config, TestRng::deterministic_rng(RngAlgorithm::ChaCha))
+}
+
+/// Scalars skewed towards the special cases: zero, one, small values and
+/// uniformly random field elements.
+fn scalar() -> impl Strategy<Value = BigInteger256> {
+    prop_oneof![
+        Just(Fr::zero().into_repr()),
+        Just(Fr::one().into_repr()),
+        (2..16u64).prop_map(|s| Fr::from(s).into_repr()),
+        any::<[u8; 32]>().prop_map(|bytes| Fr::from_le_bytes_mod_order(&bytes).into_repr()),
+    ]
+}
+
+/// Seeds of the bases `seed * g`, drawn from a small range so that bases are
+/// often repeated or negations of each other, and include the identity.
+fn base_seed() -> impl Strategy<Value = i64> {
+    -8..8i64
+}
+
+fn bases_from_seeds(seeds: &[i64]) -> Vec<G1Affine> {
+    let g = G1Affine::prime_subgroup_generator();
+    let bases: Vec<_> = seeds
+        .iter()
+        .map(|&seed| {
+            let base = g.mul(BigInteger256::from(seed.unsigned_abs()));
+            if seed < 0 { -base } else { base }
+        })
+        .collect();
+    <G1Affine as AffineCurve>::Projective::batch_normalization_into_affine(&bases)
+}
+
+/// Up to 80 `(base seed, scalar)` terms, crossing the small-input threshold.
+fn msm_terms() -> impl Strategy<Value = (Vec<i64>, Vec<BigInteger256>)> {
+    vec((base_seed(), scalar()), 0..80).prop_map(|terms| terms.into_iter().unzip())
+}
+
+#[test]
+fn prop_multi_scalar_mul_matches_naive_msm() {
+    runner()
+        .run(&msm_terms(), |(seeds, scalars)| {
+            let bases = bases_from_seeds(&seeds);
+            prop_assert_eq!(multi_scalar_mul(&bases, &scalars), naive_msm(&bases, &scalars));
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn prop_msm_variants_agree() {
+    runner()
+        .run(&msm_terms(), |(seeds, scalars)| {
+            let bases = bases_from_seeds(&seeds);
+            let expected = multi_scalar_mul(&bases, &scalars);
+            prop_assert_eq!(
+                multi_scalar_mul_with_reduction(&bases, &scalars, ReductionMode::BatchNormalizedMixed),
+                expected
+            );
+            prop_assert_eq!(multi_scalar_mul_nofilter(&bases, &scalars), expected);
+            prop_assert_eq!(MsmPrecomp::new(&bases).eval(&scalars), expected);
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn prop_addition_backends_agree() {
+    let pairs = (1..48usize).prop_flat_map(|len| (vec(base_seed(), len), vec((0..len, 0..len), 0..64)));
+    runner()
+        .run(&pairs, |(seeds, index_pairs)| {
+            let points = bases_from_seeds(&seeds);
+            let (first_idx, second_idx): (Vec<_>, Vec<_>) = index_pairs.into_iter().unzip();
+            assert_all_addition_backends_agree::<g1::Parameters>(&points, &first_idx, &second_idx);
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn prop_batch_inverse_lenient_matches_inverse() {
+    let elems = vec(prop_oneof![Just(0u64), any::<u64>()], 0..64);
+    runner()
+        .run(&elems, |elems| {
+            let elems: Vec<Fr> = elems.into_iter().map(Fr::from).collect();
+            let (inverses, zeros) = batch_inverse_lenient(&elems);
+            for (i, e) in elems.iter().enumerate() {
+                prop_assert_eq!(inverses[i], e.inverse());
+                prop_assert_eq!(zeros.contains(&i), e.is_zero());
+            }
+            Ok(())
+        })
+        .unwrap();
+}
@@ -0,0 +1,113 @@
+use ark_bls12_381::G1Affine;
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use wasm_bindgen::prelude::*;
+
+use crate::msm;
+
+#[wasm_bindgen]
+pub struct PointVectorInput {
+    point_vec: Vec<<<G1Affine as AffineCurve>::Projective as ProjectiveCurve>::Affine>,
+}
+
+#[wasm_bindgen]
+impl PointVectorInput {
+    #[wasm_bindgen(constructor)]
+    pub fn new(size: usize) -> Self {
+        let (point_vec, _) = msm::generate_msm_inputs(size);
+
+        Self {
+            point_vec,
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub struct ScalarVectorInput {
+    scalar_vec: Vec<<<G1Affine as AffineCurve>::ScalarField as PrimeField>::BigInt>,
+}
+
+#[wasm_bindgen]
+impl ScalarVectorInput {
+    #[wasm_bindgen(constructor)]
+    pub fn new(size: usize) -> Self {
+        let (_, scalar_vec) = msm::generate_msm_inputs(size);
+
+        Self {
+            scalar_vec,
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn compute_msm(point_vec: PointVectorInput, scalar_vec: ScalarVectorInput) {
+    msm::compute_msm(point_vec.point_vec, scalar_vec.scalar_vec);
+}
+
+/// Curve ids accepted by [`msm_for_curve`].
+pub const CURVE_BLS12_381_G1: u8 = 0;
+pub const CURVE_BLS12_381_G2: u8 = 1;
+pub const CURVE_BN254_G1: u8 = 2;
+
+/// First byte of the output of [`msm_for_curve`].
+pub const STATUS_OK: u8 = 0;
+pub const STATUS_UNKNOWN_CURVE: u8 = 1;
+pub const STATUS_INVALID_INPUT: u8 = 2;
+
+/// MSM on the curve named by `curve_id`, keeping the JS surface to a single
+/// function for all the supported curves.
+///
+/// `bases` and `scalars` are the canonical serializations of a `Vec` of
+/// affine points and of a `Vec` of scalar field elements. The output is a
+/// status byte, followed on success by the serialized affine result.
+#[wasm_bindgen]
+pub fn msm_for_curve(curve_id: u8, bases: &[u8], scalars: &[u8]) -> Vec<u8> {
+    let res = match curve_id {
+        CURVE_BLS12_381_G1 => msm_serialized::<ark_bls12_381::G1Affine>(bases, scalars),
+        CURVE_BLS12_381_G2 => msm_serialized::<ark_bls12_381::G2Affine>(bases, scalars),
+        CURVE_BN254_G1 => msm_serialized::<ark_bn254::G1Affine>(bases, scalars),
+        _ => return vec![STATUS_UNKNOWN_CURVE],
+    };
+    res.unwrap_or_else(|_| vec![STATUS_INVALID_INPUT])
+}
+
+fn msm_serialized<G: AffineCurve>(bases: &[u8], scalars: &[u8]) -> Result<Vec<u8>, SerializationError> {
+    let bases = Vec::<G>::deserialize(bases)?;
+    let scalars: Vec<_> = Vec::<G::ScalarField>::deserialize(scalars)?
+        .iter()
+        .map(|s| s.into_repr())
+        .collect();
+
+    let mut out = vec![STATUS_OK];
+    msm::multi_scalar_mul(&bases, &scalars).into_affine().serialize(&mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+fn check_msm_for_curve<G: AffineCurve>(curve_id: u8) {
+    use ark_ff::UniformRand;
+
+    let mut rng = ark_std::test_rng();
+    let bases: Vec<G> = (0..50).map(|_| G::Projective::rand(&mut rng).into_affine()).collect();
+    let scalars: Vec<G::ScalarField> = (0..50).map(|_| G::ScalarField::rand(&mut rng)).collect();
+    let (mut bases_bytes, mut scalars_bytes) = (Vec::new(), Vec::new());
+    bases.serialize(&mut bases_bytes).unwrap();
+    scalars.serialize(&mut scalars_bytes).unwrap();
+
+    let out = msm_for_curve(curve_id, &bases_bytes, &scalars_bytes);
+    assert_eq!(out[0], STATUS_OK);
+    let scalars: Vec<_> = scalars.iter().map(|s| s.into_repr()).collect();
+    let expected = msm::multi_scalar_mul(&bases, &scalars).into_affine();
+    assert_eq!(G::deserialize(&out[1..]).unwrap(), expected);
+}
+
+#[test]
+fn test_msm_for_curve() {
+    check_msm_for_curve::<ark_bls12_381::G1Affine>(CURVE_BLS12_381_G1);
+    check_msm_for_curve::<ark_bls12_381::G2Affine>(CURVE_BLS12_381_G2);
+    check_msm_for_curve::<ark_bn254::G1Affine>(CURVE_BN254_G1);
+
+    assert_eq!(msm_for_curve(42, &[], &[]), vec![STATUS_UNKNOWN_CURVE]);
+    assert_eq!(msm_for_curve(CURVE_BLS12_381_G1, &[1, 2, 3], &[]), vec![STATUS_INVALID_INPUT]);
+}